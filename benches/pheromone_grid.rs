@@ -0,0 +1,137 @@
+// Compares the flat `PheromoneLayer` with the nested `Vec<Vec<f32>>` layout
+// it replaced, at the default world size.
+//
+//     cargo bench --bench pheromone_grid
+
+use ants::pheromones::PheromoneLayer;
+use bevy::math::Vec2;
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const WIDTH: usize = 1728;
+const HEIGHT: usize = 1050;
+
+// The previous layout, indexed `[x][y]`
+fn nested_grid() -> Vec<Vec<f32>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    (0..WIDTH)
+        .map(|_| (0..HEIGHT).map(|_| rng.gen_range(0.0..1.0)).collect())
+        .collect()
+}
+
+fn flat_layer() -> PheromoneLayer {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut layer = PheromoneLayer::new(WIDTH, HEIGHT);
+    for value in layer.values_mut() {
+        *value = rng.gen_range(0.0..1.0);
+    }
+    layer
+}
+
+// Positions an ant colony would sense at: short rays from random spots
+fn sensing_positions() -> Vec<Vec2> {
+    let mut rng = StdRng::seed_from_u64(1);
+    (0..5000)
+        .flat_map(|_| {
+            let start = Vec2::new(
+                rng.gen_range(0.0..WIDTH as f32 - 10.),
+                rng.gen_range(0.0..HEIGHT as f32 - 10.),
+            );
+            let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+            (1..=6).map(move |distance| start + direction * distance as f32)
+        })
+        .collect()
+}
+
+fn decay(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decay");
+    let mut nested = nested_grid();
+    group.bench_function("nested", |b| {
+        b.iter(|| {
+            for column in nested.iter_mut() {
+                for value in column.iter_mut() {
+                    *value *= black_box(0.999);
+                }
+            }
+        })
+    });
+    let mut flat = flat_layer();
+    group.bench_function("flat", |b| {
+        b.iter(|| {
+            for value in flat.values_mut() {
+                *value *= black_box(0.999);
+            }
+        })
+    });
+    group.finish();
+}
+
+fn sensing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sensing");
+    let positions = sensing_positions();
+    let nested = nested_grid();
+    group.bench_function("nested", |b| {
+        b.iter(|| {
+            positions
+                .iter()
+                .map(|position| nested[position.x as usize][position.y as usize])
+                .sum::<f32>()
+        })
+    });
+    let flat = flat_layer();
+    group.bench_function("flat", |b| {
+        b.iter(|| {
+            positions
+                .iter()
+                .map(|position| flat.get(position.x as usize, position.y as usize))
+                .sum::<f32>()
+        })
+    });
+    group.bench_function("flat_bilinear", |b| {
+        b.iter(|| {
+            positions
+                .iter()
+                .map(|position| flat.sample(*position))
+                .sum::<f32>()
+        })
+    });
+    group.finish();
+}
+
+// Filling an RGBA texture, whose rows run along x. The nested version keeps
+// the index loops of the old texture update.
+#[allow(clippy::needless_range_loop)]
+fn texture_fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("texture_fill");
+    let mut data = vec![0u8; WIDTH * HEIGHT * 4];
+    let nested = nested_grid();
+    group.bench_function("nested", |b| {
+        b.iter(|| {
+            for y in 0..HEIGHT {
+                for x in 0..WIDTH {
+                    let index = ((HEIGHT - 1 - y) * WIDTH + x) * 4;
+                    data[index + 3] = (nested[x][y] * 255.0) as u8;
+                }
+            }
+        })
+    });
+    let flat = flat_layer();
+    group.bench_function("flat", |b| {
+        b.iter(|| {
+            for (row, pixels) in flat
+                .values()
+                .chunks_exact(WIDTH)
+                .zip(data.chunks_exact_mut(WIDTH * 4).rev())
+            {
+                for (value, pixel) in row.iter().zip(pixels.chunks_exact_mut(4)) {
+                    pixel[3] = (value * 255.0) as u8;
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, decay, sensing, texture_fill);
+criterion_main!(benches);
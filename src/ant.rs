@@ -1,12 +1,22 @@
 use crate::components::ant::Ant;
-use crate::components::carrying_food::CarryingFood;
+use crate::components::ant_state::AntState;
+use crate::components::colony_id::ColonyId;
+use crate::components::depositing::Depositing;
 use crate::components::direction::Direction;
-use crate::components::food::Food;
+use crate::components::nest::Nest;
 use crate::components::position::Position;
-use crate::systems::ant_lifetime_reset_system::{
-    MAX_LIFETIME, MIN_LIFETIME, ant_lifetime_reset_system,
-};
-use crate::systems::ant_rebirth_system::ant_rebirth_system;
+use crate::components::previous_position::PreviousPosition;
+use crate::components::role::Role;
+use crate::components::travelled::Travelled;
+use crate::config::SimConfig;
+use crate::nest::spawn_nest;
+use crate::pheromones::PheromoneSystems;
+use crate::resources::foraging_strategies::ForagingStrategies;
+use crate::resources::sim_rng::{RngStream, SimRng};
+use crate::systems::ant_aging_system::ant_aging_system;
+use crate::systems::ant_goal_system::ant_goal_system;
+use crate::systems::colony_system::colony_system;
+use crate::systems::death_system::death_system;
 use crate::systems::follow_pheromone_system::follow_pheromones_system;
 use bevy::math::Vec2;
 use bevy::prelude::*;
@@ -18,83 +28,108 @@ pub struct AntPlugin;
 
 impl Plugin for AntPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup).add_systems(
-            Update,
-            (
-                ant_goal_system,
-                follow_pheromones_system,
-                ant_rebirth_system,
-                ant_lifetime_reset_system,
-                sync_transform_with_position,
-            ),
-        );
+        app.init_resource::<ForagingStrategies>()
+            .add_event::<AntStateChanged>()
+            .add_systems(Startup, setup.after(spawn_nest))
+            .add_systems(FixedFirst, record_previous_position)
+            .add_systems(
+                FixedUpdate,
+                (
+                    // Ants steer by the goal they reached this tick, and lay
+                    // trails where their strategy took them
+                    (ant_goal_system, follow_pheromones_system)
+                        .chain()
+                        .before(PheromoneSystems),
+                    ant_aging_system,
+                    colony_system,
+                ),
+            )
+            // After every cause of death had its say this tick
+            .add_systems(FixedPostUpdate, death_system);
     }
 }
 
 fn setup(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    sim_rng: Res<SimRng>,
+    config: Res<SimConfig>,
+    nests: Query<(&Position, &ColonyId), With<Nest>>,
 ) {
-    for _ in 0..5000 {
-        let mut rng = rand::thread_rng();
-        // Random lifetime between 30 and 60 seconds
-        let lifetime_secs = rng.gen_range(MIN_LIFETIME..=MAX_LIFETIME);
-        let mut rng = rand::thread_rng();
-        let random_angle = rng.gen_range(0.0..TAU);
+    let mut rng = sim_rng.stream(RngStream::Spawn);
 
-        commands.spawn((
-            Mesh2d(meshes.add(Rectangle::new(3., 3.))),
-            MeshMaterial2d(materials.add(Color::srgb(0.65, 0.145, 0.145))),
-            Transform::from_xyz(0., 0., 0.),
-            Ant {
-                lifetime: Timer::new(Duration::from_secs_f32(lifetime_secs), TimerMode::Once),
-            },
-            Position {
-                position: Vec2::new(0.0, 0.0),
-            }, // Initial position
-            Direction {
-                direction: Vec2::new(random_angle.cos(), random_angle.sin()).normalize(),
-            },
-        ));
+    // Every colony starts with the same population at its nest
+    for (nest, colony) in nests.iter().sort::<&ColonyId>() {
+        for _ in 0..config.ants.count {
+            commands.spawn(ant_bundle(nest.position, *colony, &config, &mut rng));
+        }
     }
 }
-// System for handling ant goals (finding food or returning to nest)
-pub fn ant_goal_system(
-    mut commands: Commands,
-    mut query: Query<(Entity, &Position, Option<&CarryingFood>), With<Ant>>,
-    food_positions: Query<&Position, With<Food>>,
-) {
-    // Collect food positions into a Vec to avoid query conflicts
-    let food_positions: Vec<Vec2> = food_positions
-        .iter()
-        .map(|position| Vec2::new(position.position.x, position.position.y))
-        .collect();
 
-    for (entity, position, carrying_food) in query.iter_mut() {
-        if carrying_food.is_none() {
-            // Check if ant found food
-            let found_food = food_positions
-                .iter()
-                .any(|&food_pos| food_pos.distance(position.position) < 5.0);
+// Components of a newly hatched ant of `colony` at `position`, heading in a
+// random direction
+pub fn ant_bundle(
+    position: Vec2,
+    colony: ColonyId,
+    config: &SimConfig,
+    rng: &mut impl Rng,
+) -> impl Bundle {
+    // Random lifetime within the configured range
+    let lifetime_secs = rng.gen_range(config.ants.min_lifetime..=config.ants.max_lifetime);
+    let random_angle = rng.gen_range(0.0..TAU);
+    let role = if rng.gen_bool(config.alarm.defender_share as f64) {
+        Role::Defender
+    } else {
+        Role::Worker
+    };
+
+    (
+        Ant {
+            lifetime: Timer::new(Duration::from_secs_f32(lifetime_secs), TimerMode::Once),
+        },
+        colony,
+        Position { position },
+        PreviousPosition { position },
+        Direction {
+            direction: Vec2::new(random_angle.cos(), random_angle.sin()).normalize(),
+        },
+        role,
+        Travelled::default(),
+        Depositing::default(),
+        AntState::default(),
+    )
+}
 
-            if found_food {
-                commands.entity(entity).insert(CarryingFood);
-            }
-        } else {
-            // Check if ant reached the nest
-            let reached_nest = position.position.length() < 10.0;
+// Sent whenever an ant changes its `AntState`
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct AntStateChanged {
+    pub ant: Entity,
+    pub colony: ColonyId,
+    pub from: AntState,
+    pub to: AntState,
+}
 
-            if reached_nest {
-                // Change goal back to finding food
-                commands.entity(entity).remove::<CarryingFood>();
-            }
-        }
+// Moves an ant to state `to`, announcing it if that is a change
+pub(crate) fn set_state(
+    events: &mut EventWriter<AntStateChanged>,
+    ant: Entity,
+    colony: ColonyId,
+    state: &mut AntState,
+    to: AntState,
+) {
+    if *state != to {
+        events.send(AntStateChanged {
+            ant,
+            colony,
+            from: *state,
+            to,
+        });
+        *state = to;
     }
 }
 
-fn sync_transform_with_position(mut query: Query<(&Position, &mut Transform)>) {
-    for (position, mut transform) in query.iter_mut() {
-        transform.translation = position.position.extend(0.0); // Update position
+// Remember where each ant was before this tick so the viewer can interpolate
+fn record_previous_position(mut query: Query<(&Position, &mut PreviousPosition)>) {
+    for (position, mut previous) in query.iter_mut() {
+        previous.position = position.position;
     }
 }
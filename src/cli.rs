@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+// Command line options understood by the `ants` binary
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub headless: bool,
+    pub ticks: Option<u64>,
+    pub config: Option<PathBuf>,
+    // `section.key=value` assignments applied on top of the config file
+    pub overrides: Vec<String>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("{name} expects a value"));
+            match arg.as_str() {
+                "--headless" => parsed.headless = true,
+                "--ticks" => {
+                    let value = value("--ticks")?;
+                    let ticks = value
+                        .parse()
+                        .map_err(|_| format!("--ticks expects a number, got `{value}`"))?;
+                    parsed.ticks = Some(ticks);
+                }
+                "--config" => parsed.config = Some(PathBuf::from(value("--config")?)),
+                "--set" => parsed.overrides.push(value("--set")?),
+                // Shortcuts for frequently overridden settings
+                "--seed" => parsed.overrides.push(format!("simulation.seed={}", value("--seed")?)),
+                "--tick-rate" => parsed
+                    .overrides
+                    .push(format!("simulation.tick_rate={}", value("--tick-rate")?)),
+                "--sensing" => parsed
+                    .overrides
+                    .push(format!("sensing.model={}", value("--sensing")?)),
+                other => return Err(format!("unknown argument `{other}`")),
+            }
+        }
+
+        if parsed.ticks.is_some() && !parsed.headless {
+            return Err("--ticks can only be used together with --headless".to_string());
+        }
+
+        Ok(parsed)
+    }
+}
+
+#[test]
+fn parses_headless_tick_count() {
+    let args = Args::parse_from(["--headless", "--ticks", "250"].map(String::from)).unwrap();
+
+    assert_eq!(
+        args,
+        Args {
+            headless: true,
+            ticks: Some(250),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn shortcuts_become_config_overrides() {
+    let args = Args::parse_from(
+        ["--seed", "7", "--set", "ants.count=10", "--tick-rate", "30", "--sensing", "probes"]
+            .map(String::from),
+    )
+    .unwrap();
+
+    assert_eq!(
+        args.overrides,
+        [
+            "simulation.seed=7",
+            "ants.count=10",
+            "simulation.tick_rate=30",
+            "sensing.model=probes"
+        ]
+    );
+}
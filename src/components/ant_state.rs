@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+
+// What an ant is currently doing. Its speed and whether it lays its trail
+// depend on the state, see `StateConfig`.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AntState {
+    // Searching for food without a trail to go by
+    #[default]
+    Exploring,
+    // Searching for food along a food trail
+    FollowingTrail,
+    // Carrying food back to the nest
+    ReturningWithFood,
+    // Catching its breath at the nest after a delivery
+    Resting,
+    // Running from an alarm
+    Fleeing,
+}
+
+// Counts down an ant's rest at the nest
+#[derive(Component, Debug)]
+pub struct Rest {
+    pub timer: Timer,
+}
+
+impl Rest {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
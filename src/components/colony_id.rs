@@ -0,0 +1,5 @@
+use bevy::prelude::*;
+
+// The colony an ant or nest belongs to, indexing `SimConfig::colonies`
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ColonyId(pub usize);
@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+// Whether the ant's strategy last asked it to lay its trail
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Depositing {
+    pub active: bool,
+}
+
+impl Default for Depositing {
+    fn default() -> Self {
+        Self { active: true }
+    }
+}
@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+
+// Marks an ant that died this tick. Ants can die of several causes at once, so
+// they are only counted and despawned once, by `death_system`.
+#[derive(Component, Debug)]
+pub struct Dying;
@@ -1,5 +1,26 @@
 use bevy::prelude::*;
 
+// A food source holding a finite amount of food; each pickup takes one unit
 #[derive(Component)]
-pub struct Food;
+pub struct Food {
+    pub remaining: u32,
+    pub initial: u32,
+}
 
+impl Food {
+    pub fn new(amount: u32) -> Self {
+        Self {
+            remaining: amount,
+            initial: amount,
+        }
+    }
+
+    // Fraction of the initial amount still left, between 0 and 1
+    pub fn fraction_remaining(&self) -> f32 {
+        if self.initial == 0 {
+            0.0
+        } else {
+            self.remaining as f32 / self.initial as f32
+        }
+    }
+}
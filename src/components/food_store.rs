@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+// Food stockpiled in a nest; ants eat from it and new ants hatch from it
+#[derive(Component)]
+pub struct FoodStore {
+    pub amount: f32,
+}
@@ -0,0 +1,9 @@
+use crate::config::HazardKind;
+use bevy::prelude::*;
+
+// A danger ants raise the alarm about while they are within `radius` of it
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub radius: f32,
+}
@@ -1,6 +1,17 @@
 pub mod ant;
+pub mod ant_state;
 pub mod carrying_food;
+pub mod colony_id;
+pub mod depositing;
 pub mod direction;
+pub mod dying;
 pub mod food;
+pub mod food_store;
+pub mod hazard;
+pub mod nest;
+pub mod obstacle;
 pub mod position;
-pub mod reset_lifetime;
+pub mod previous_position;
+pub mod role;
+pub mod strategy;
+pub mod travelled;
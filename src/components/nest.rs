@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+// A colony's nest; ants start here and bring food back to it
+#[derive(Component)]
+pub struct Nest {
+    // Distance at which an ant drops its food at the nest
+    pub radius: f32,
+}
@@ -0,0 +1,8 @@
+use crate::config::ObstacleShape;
+use bevy::prelude::*;
+
+// One configured obstacle, spawned so the viewer can draw it
+#[derive(Component, Debug, Clone)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+}
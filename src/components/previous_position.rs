@@ -0,0 +1,7 @@
+use bevy::prelude::*;
+
+// Position at the start of the current fixed tick, used to interpolate rendering
+#[derive(Component)]
+pub struct PreviousPosition {
+    pub position: Vec2,
+}
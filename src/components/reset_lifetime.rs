@@ -1,6 +0,0 @@
-use bevy::prelude::*;
-
-// Marker component to indicate an ant's lifetime should be reset
-#[derive(Component)]
-pub struct ResetLifetime;
-
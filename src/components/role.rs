@@ -0,0 +1,10 @@
+use bevy::prelude::*;
+
+// How an ant responds to alarm pheromone, decided when it hatches
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    // Flees from alarms
+    Worker,
+    // Rallies toward alarms
+    Defender,
+}
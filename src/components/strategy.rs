@@ -0,0 +1,13 @@
+use crate::foraging::ForagingStrategy;
+use bevy::prelude::*;
+use std::sync::Arc;
+
+// Steers one ant with its own strategy instead of its colony's
+#[derive(Component, Clone)]
+pub struct Strategy(pub Arc<dyn ForagingStrategy>);
+
+impl Strategy {
+    pub fn new(strategy: impl ForagingStrategy) -> Self {
+        Self(Arc::new(strategy))
+    }
+}
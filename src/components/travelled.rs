@@ -0,0 +1,8 @@
+use bevy::prelude::*;
+
+// Distance an ant has walked since it last touched its nest or a food source.
+// The further it is, the weaker the trail it leaves.
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct Travelled {
+    pub distance: f32,
+}
@@ -0,0 +1,885 @@
+use crate::components::ant_state::AntState;
+use crate::resources::world_bounds::WorldBounds;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Config file picked up from the working directory when no path is given
+pub const DEFAULT_CONFIG_PATH: &str = "ants.toml";
+
+// Every tunable of the simulation. Each section may be omitted from the
+// config file, in which case its defaults are used.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+    pub simulation: SimulationConfig,
+    pub world: WorldConfig,
+    pub window: WindowConfig,
+    pub ants: AntConfig,
+    pub sensing: SensingConfig,
+    pub pheromones: PheromoneConfig,
+    pub food: FoodConfig,
+    pub nest: NestConfig,
+    pub alarm: AlarmConfig,
+    pub states: StateConfig,
+    // One entry per competing colony, each given as a `[[colonies]]` table
+    pub colonies: Vec<ColonyConfig>,
+    // Walls and rocks, each given as an `[[obstacles]]` table
+    pub obstacles: Vec<ObstacleShape>,
+    // Dangers ants raise the alarm about, each given as a `[[hazards]]` table
+    pub hazards: Vec<HazardConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    // Random seed; a random one is chosen when missing
+    pub seed: Option<u64>,
+    // Fixed simulation ticks per simulated second
+    pub tick_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorldConfig {
+    pub width: f32,
+    pub height: f32,
+    // PNG image describing the arena; its size replaces `width` and `height`
+    pub map: Option<PathBuf>,
+    // What happens at the edges of the world
+    pub boundary: BoundaryMode,
+}
+
+// Edge behaviour of the world, shared by movement, sensing and deposition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoundaryMode {
+    // Opposite edges are joined, so the world is a torus
+    #[default]
+    Wrap,
+    // Edges stop ants, which turn around
+    Wall,
+    // Ants bounce off edges like light off a mirror
+    Reflect,
+    // Ants walking off an edge are lost
+    Void,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowConfig {
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AntConfig {
+    // Initial population; afterwards ants hatch from the nest's food store
+    pub count: usize,
+    // World units per simulated second
+    pub speed: f32,
+    // Lifetime range in simulated seconds
+    pub min_lifetime: f32,
+    pub max_lifetime: f32,
+    // Food each ant eats from the nest's store per simulated second
+    pub food_consumption: f32,
+    // Share of ants dying per simulated second while the store is empty
+    pub starvation_rate: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SensingConfig {
+    // How ants read the pheromone grid and steer by it
+    pub model: SensingModel,
+    // Half-width of the sensing cone, in degrees
+    pub view_angle: f32,
+    // How far ahead ants sample pheromones, in world units
+    pub view_radius: u32,
+    // Degrees between the centre probe and each side probe
+    pub probe_angle: f32,
+    // World units from an ant to its probes
+    pub probe_distance: f32,
+    // Degrees per simulated second ants turn when only one side probe senses
+    // a trail
+    pub turn_rate: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensingModel {
+    // Every degree of the view cone at every distance up to `view_radius`;
+    // ants head straight for the strongest reading
+    #[default]
+    Cone,
+    // Three probes, ahead and `probe_angle` to either side; ants turn toward
+    // the stronger side in proportion to the difference
+    Probes,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PheromoneConfig {
+    // World units covered by the side of one grid cell
+    pub cell_size: f32,
+    // Simulated seconds for trails left by ants looking for food to halve
+    pub nest_half_life: f32,
+    // Simulated seconds for trails left by ants carrying food to halve
+    pub food_half_life: f32,
+    // Levels below this evaporate completely
+    pub evaporation_floor: f32,
+    // Pheromone deposited per simulated second by ants looking for food
+    pub nest_deposit: f32,
+    // Pheromone deposited per simulated second by ants carrying food
+    pub food_deposit: f32,
+    // World units an ant walks away from its nest or food before its trail
+    // is half as strong
+    pub deposit_half_distance: f32,
+    // Simulated seconds between diffusion passes
+    pub diffusion_interval: f32,
+    // Width in cells of the square neighbourhood pheromone spreads over; odd
+    pub diffusion_kernel: usize,
+    // Share of each cell replaced by its neighbourhood average per pass
+    pub diffusion_rate: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FoodConfig {
+    // Number of sources, for every placement except `fixed`
+    pub count: usize,
+    // Units of food in each source; a source disappears when it runs out
+    pub amount: u32,
+    // Distance at which an ant picks up food
+    pub pickup_radius: f32,
+    // Simulated seconds before a depleted source is replaced; never when missing
+    pub respawn_delay: Option<f32>,
+    pub placement: FoodPlacement,
+}
+
+// Where food sources are put, selected with `strategy` in `[food.placement]`.
+// Sources replacing depleted ones reuse the spot for `line`, `fixed` and
+// `ring`, and get a fresh random spot for `uniform` and `clusters`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case", deny_unknown_fields)]
+pub enum FoodPlacement {
+    // Evenly spaced on a horizontal line through the middle of the world
+    #[default]
+    Line,
+    // Exactly the listed world positions
+    Fixed { positions: Vec<[f32; 2]> },
+    // Uniformly random over the whole world
+    Uniform,
+    // Random spots within `radius` of a few randomly placed patch centres
+    Clusters { clusters: usize, radius: f32 },
+    // Evenly spaced on a circle of `radius` around the first colony's nest
+    Ring { radius: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NestConfig {
+    // Distance at which an ant drops its food at the nest
+    pub radius: f32,
+    // Food in the store when the simulation starts
+    pub initial_food: f32,
+    // Store level above which new ants hatch
+    pub birth_threshold: f32,
+    // Food taken from the store for every new ant
+    pub birth_cost: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlarmConfig {
+    // Alarm pheromone laid per simulated second by an ant inside a hazard
+    pub deposit: f32,
+    // Simulated seconds for alarm levels to halve
+    pub half_life: f32,
+    // Alarm level ants react to
+    pub threshold: f32,
+    // Share of hatching ants that rally toward alarms instead of fleeing them
+    pub defender_share: f32,
+    // Share of ants in a poison zone or caught by a predator dying per
+    // simulated second
+    pub lethality: f32,
+    // World units per simulated second
+    pub predator_speed: f32,
+}
+
+// How ants behave in each `AntState`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateConfig {
+    // Simulated seconds an ant rests at the nest after delivering food; 0
+    // sends it straight back out
+    pub rest_duration: f32,
+    pub exploring: StateRule,
+    pub following_trail: StateRule,
+    pub returning_with_food: StateRule,
+    pub resting: StateRule,
+    pub fleeing: StateRule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StateRule {
+    // Multiple of `ants.speed`
+    pub speed: f32,
+    // Whether ants lay their trail in this state
+    pub deposit: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColonyConfig {
+    // World position of the colony's nest. When missing, a single nest sits in
+    // the centre of the world and several are spread on a circle around it.
+    pub nest: Option<[f32; 2]>,
+    // Ant color as sRGB components between 0 and 1
+    pub color: [f32; 3],
+}
+
+// An area ants cannot walk through, sense across or leave pheromone on,
+// selected with `shape` in its `[[obstacles]]` table. Coordinates are in world
+// units.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ObstacleShape {
+    // Axis-aligned box, e.g. a wall
+    Rectangle { center: [f32; 2], size: [f32; 2] },
+    Circle { center: [f32; 2], radius: f32 },
+    // Closed polygon through the listed corners
+    Polygon { points: Vec<[f32; 2]> },
+}
+
+// A danger of the given `kind` covering a circle, in world units
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HazardConfig {
+    pub kind: HazardKind,
+    pub center: [f32; 2],
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HazardKind {
+    // Harmless, but ants inside raise the alarm all the same
+    Marker,
+    // A zone that kills ants inside it
+    Poison,
+    // Wanders the world and kills the ants it catches
+    Predator,
+}
+
+impl HazardKind {
+    pub fn is_lethal(self) -> bool {
+        self != HazardKind::Marker
+    }
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            simulation: SimulationConfig::default(),
+            world: WorldConfig::default(),
+            window: WindowConfig::default(),
+            ants: AntConfig::default(),
+            sensing: SensingConfig::default(),
+            pheromones: PheromoneConfig::default(),
+            food: FoodConfig::default(),
+            nest: NestConfig::default(),
+            alarm: AlarmConfig::default(),
+            states: StateConfig::default(),
+            colonies: vec![ColonyConfig::default()],
+            obstacles: Vec::new(),
+            hazards: Vec::new(),
+        }
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            tick_rate: 60.0,
+        }
+    }
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            width: 1728.,
+            height: 1050.,
+            map: None,
+            boundary: BoundaryMode::Wrap,
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 1728.,
+            height: 1050.,
+        }
+    }
+}
+
+impl Default for AntConfig {
+    fn default() -> Self {
+        Self {
+            count: 5000,
+            speed: 60.0,
+            min_lifetime: 24.,
+            max_lifetime: 99.,
+            food_consumption: 0.001,
+            starvation_rate: 0.05,
+        }
+    }
+}
+
+impl Default for SensingConfig {
+    fn default() -> Self {
+        Self {
+            model: SensingModel::Cone,
+            view_angle: 45.0,
+            view_radius: 6,
+            probe_angle: 30.0,
+            probe_distance: 9.0,
+            turn_rate: 360.0,
+        }
+    }
+}
+
+impl Default for PheromoneConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 1.0,
+            nest_half_life: 1155.0,
+            food_half_life: 1155.0,
+            evaporation_floor: 0.001,
+            nest_deposit: 1.2,
+            food_deposit: 3.0, // 2.5 times stronger for Food pheromone
+            deposit_half_distance: 500.0,
+            diffusion_interval: 0.1,
+            diffusion_kernel: 3,
+            diffusion_rate: 0.2,
+        }
+    }
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self {
+            deposit: 5.0,
+            half_life: 3.0,
+            threshold: 0.05,
+            defender_share: 0.1,
+            lethality: 0.5,
+            predator_speed: 20.0,
+        }
+    }
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self {
+            rest_duration: 2.0,
+            exploring: StateRule::default(),
+            following_trail: StateRule::default(),
+            returning_with_food: StateRule::default(),
+            resting: StateRule {
+                speed: 0.0,
+                deposit: false,
+            },
+            // Panicking ants run, and don't lead others along their escape
+            fleeing: StateRule {
+                speed: 1.5,
+                deposit: false,
+            },
+        }
+    }
+}
+
+impl StateConfig {
+    pub fn rule(&self, state: AntState) -> StateRule {
+        match state {
+            AntState::Exploring => self.exploring,
+            AntState::FollowingTrail => self.following_trail,
+            AntState::ReturningWithFood => self.returning_with_food,
+            AntState::Resting => self.resting,
+            AntState::Fleeing => self.fleeing,
+        }
+    }
+}
+
+impl Default for StateRule {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            deposit: true,
+        }
+    }
+}
+
+impl Default for FoodConfig {
+    fn default() -> Self {
+        Self {
+            count: 5,
+            amount: 500,
+            pickup_radius: 5.0,
+            respawn_delay: None,
+            placement: FoodPlacement::Line,
+        }
+    }
+}
+
+impl Default for NestConfig {
+    fn default() -> Self {
+        Self {
+            radius: 10.0,
+            initial_food: 100.0,
+            birth_threshold: 200.0,
+            birth_cost: 10.0,
+        }
+    }
+}
+
+impl Default for ColonyConfig {
+    fn default() -> Self {
+        Self {
+            nest: None,
+            color: [0.65, 0.145, 0.145],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io { path: PathBuf, source: std::io::Error },
+    Parse { origin: String, source: toml::de::Error },
+    Override(String),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io { path, source } => {
+                write!(f, "cannot read config file {}: {source}", path.display())
+            }
+            ConfigError::Parse { origin, source } => write!(f, "invalid config in {origin}: {source}"),
+            ConfigError::Override(message) => write!(f, "invalid override: {message}"),
+            ConfigError::Invalid(problems) => {
+                write!(f, "invalid config:")?;
+                for problem in problems {
+                    write!(f, "\n  - {problem}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl SimConfig {
+    // Loads the config from `path`, or from `DEFAULT_CONFIG_PATH` if it exists,
+    // applies `key.path=value` overrides on top and validates the result
+    pub fn load(path: Option<&Path>, overrides: &[String]) -> Result<Self, ConfigError> {
+        let (source, origin) = match Self::resolve_path(path) {
+            Some(path) => (read_config(&path)?, path.display().to_string()),
+            None => (String::new(), "defaults".to_string()),
+        };
+        Self::from_toml(&source, &origin, overrides)
+    }
+
+    // The file `load` reads for the given path, if any
+    pub fn resolve_path(path: Option<&Path>) -> Option<PathBuf> {
+        match path {
+            Some(path) => Some(path.to_path_buf()),
+            None => Some(PathBuf::from(DEFAULT_CONFIG_PATH)).filter(|path| path.exists()),
+        }
+    }
+
+    pub fn from_toml(source: &str, origin: &str, overrides: &[String]) -> Result<Self, ConfigError> {
+        let parse_error = |source| ConfigError::Parse {
+            origin: origin.to_string(),
+            source,
+        };
+
+        let mut table: toml::Table = toml::from_str(source).map_err(parse_error)?;
+        for assignment in overrides {
+            apply_override(&mut table, assignment)?;
+        }
+
+        let config: SimConfig = table.try_into().map_err(parse_error)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    // Checks every value and reports all problems at once
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let positive = |problems: &mut Vec<String>, name: &str, value: f64| {
+            if !(value > 0.0 && value.is_finite()) {
+                problems.push(format!("{name} must be a positive number, got {value}"));
+            }
+        };
+
+        positive(&mut problems, "simulation.tick_rate", self.simulation.tick_rate);
+        positive(&mut problems, "world.width", self.world.width.into());
+        positive(&mut problems, "world.height", self.world.height.into());
+        positive(&mut problems, "window.width", self.window.width.into());
+        positive(&mut problems, "window.height", self.window.height.into());
+        positive(&mut problems, "ants.speed", self.ants.speed.into());
+        positive(&mut problems, "ants.min_lifetime", self.ants.min_lifetime.into());
+        positive(&mut problems, "ants.max_lifetime", self.ants.max_lifetime.into());
+        positive(&mut problems, "food.pickup_radius", self.food.pickup_radius.into());
+        positive(&mut problems, "nest.radius", self.nest.radius.into());
+        positive(&mut problems, "nest.birth_cost", self.nest.birth_cost.into());
+        positive(&mut problems, "alarm.half_life", self.alarm.half_life.into());
+        for (name, value) in [
+            ("ants.food_consumption", self.ants.food_consumption),
+            ("ants.starvation_rate", self.ants.starvation_rate),
+            ("nest.initial_food", self.nest.initial_food),
+            ("nest.birth_threshold", self.nest.birth_threshold),
+            ("alarm.deposit", self.alarm.deposit),
+            ("alarm.threshold", self.alarm.threshold),
+            ("alarm.lethality", self.alarm.lethality),
+            ("alarm.predator_speed", self.alarm.predator_speed),
+            ("states.rest_duration", self.states.rest_duration),
+            ("states.exploring.speed", self.states.exploring.speed),
+            ("states.following_trail.speed", self.states.following_trail.speed),
+            ("states.returning_with_food.speed", self.states.returning_with_food.speed),
+            ("states.resting.speed", self.states.resting.speed),
+            ("states.fleeing.speed", self.states.fleeing.speed),
+        ] {
+            if !(value >= 0.0 && value.is_finite()) {
+                problems.push(format!("{name} must not be negative, got {value}"));
+            }
+        }
+        if self.colonies.is_empty() {
+            problems.push("at least one [[colonies]] entry is required".to_string());
+        }
+        for (index, obstacle) in self.obstacles.iter().enumerate() {
+            match obstacle {
+                ObstacleShape::Rectangle { size: [w, h], .. } => {
+                    positive(&mut problems, &format!("obstacles[{index}].size"), w.min(*h).into());
+                }
+                ObstacleShape::Circle { radius, .. } => {
+                    positive(&mut problems, &format!("obstacles[{index}].radius"), (*radius).into());
+                }
+                ObstacleShape::Polygon { points } if points.len() < 3 => {
+                    problems.push(format!("obstacles[{index}].points must list at least 3 corners"));
+                }
+                _ => {}
+            }
+        }
+        for (index, hazard) in self.hazards.iter().enumerate() {
+            positive(&mut problems, &format!("hazards[{index}].radius"), hazard.radius.into());
+        }
+        if !(0.0..=1.0).contains(&self.alarm.defender_share) {
+            problems.push(format!(
+                "alarm.defender_share must be between 0 and 1, got {}",
+                self.alarm.defender_share
+            ));
+        }
+        for (index, colony) in self.colonies.iter().enumerate() {
+            // A map replaces the world size, so `ArenaMap::load` checks those
+            if let Some([x, y]) = colony.nest
+                && self.world.map.is_none()
+                && !((0.0..self.world.width).contains(&x) && (0.0..self.world.height).contains(&y))
+            {
+                problems.push(format!("colonies[{index}].nest [{x}, {y}] lies outside the world"));
+            }
+            let nest = self.nest_position(index);
+            if let Some(obstacle) = self.obstacles.iter().position(|shape| shape.contains(nest)) {
+                problems.push(format!("colonies[{index}].nest lies inside obstacles[{obstacle}]"));
+            }
+            if colony.color.iter().any(|channel| !(0.0..=1.0).contains(channel)) {
+                problems.push(format!("colonies[{index}].color channels must be between 0 and 1"));
+            }
+        }
+
+        if self.ants.min_lifetime > self.ants.max_lifetime {
+            problems.push(format!(
+                "ants.min_lifetime ({}) must not exceed ants.max_lifetime ({})",
+                self.ants.min_lifetime, self.ants.max_lifetime
+            ));
+        }
+        if self.food.amount == 0 {
+            problems.push("food.amount must be at least 1".to_string());
+        }
+        if let Some(delay) = self.food.respawn_delay {
+            positive(&mut problems, "food.respawn_delay", delay.into());
+        }
+        match &self.food.placement {
+            FoodPlacement::Fixed { positions } if positions.is_empty() => {
+                problems.push("food.placement.positions must list at least one position".to_string());
+            }
+            FoodPlacement::Fixed { positions } => {
+                for [x, y] in positions {
+                    // Checked against the map by `ArenaMap::load` instead
+                    if self.world.map.is_none()
+                        && !((0.0..self.world.width).contains(x)
+                            && (0.0..self.world.height).contains(y))
+                    {
+                        problems.push(format!("food position [{x}, {y}] lies outside the world"));
+                    }
+                    if self.obstacles.iter().any(|shape| shape.contains(Vec2::new(*x, *y))) {
+                        problems.push(format!("food position [{x}, {y}] lies inside an obstacle"));
+                    }
+                }
+            }
+            FoodPlacement::Clusters { clusters, radius } => {
+                if *clusters == 0 {
+                    problems.push("food.placement.clusters must be at least 1".to_string());
+                }
+                positive(&mut problems, "food.placement.radius", (*radius).into());
+            }
+            FoodPlacement::Ring { radius } => {
+                positive(&mut problems, "food.placement.radius", (*radius).into());
+            }
+            _ => {}
+        }
+        if !(0.0..=180.0).contains(&self.sensing.view_angle) {
+            problems.push(format!(
+                "sensing.view_angle must be between 0 and 180 degrees, got {}",
+                self.sensing.view_angle
+            ));
+        }
+        if !(self.sensing.probe_angle > 0.0 && self.sensing.probe_angle <= 180.0) {
+            problems.push(format!(
+                "sensing.probe_angle must be above 0 and at most 180 degrees, got {}",
+                self.sensing.probe_angle
+            ));
+        }
+        positive(&mut problems, "sensing.probe_distance", self.sensing.probe_distance.into());
+        if !(self.sensing.turn_rate >= 0.0 && self.sensing.turn_rate.is_finite()) {
+            problems.push(format!(
+                "sensing.turn_rate must not be negative, got {}",
+                self.sensing.turn_rate
+            ));
+        }
+        if self.sensing.view_radius == 0 {
+            problems.push("sensing.view_radius must be at least 1".to_string());
+        }
+        positive(&mut problems, "pheromones.cell_size", self.pheromones.cell_size.into());
+        positive(
+            &mut problems,
+            "pheromones.diffusion_interval",
+            self.pheromones.diffusion_interval.into(),
+        );
+        if self.pheromones.diffusion_kernel.is_multiple_of(2) {
+            problems.push(format!(
+                "pheromones.diffusion_kernel must be odd, got {}",
+                self.pheromones.diffusion_kernel
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.pheromones.diffusion_rate) {
+            problems.push(format!(
+                "pheromones.diffusion_rate must be between 0 and 1, got {}",
+                self.pheromones.diffusion_rate
+            ));
+        }
+        positive(
+            &mut problems,
+            "pheromones.deposit_half_distance",
+            self.pheromones.deposit_half_distance.into(),
+        );
+        positive(
+            &mut problems,
+            "pheromones.nest_half_life",
+            self.pheromones.nest_half_life.into(),
+        );
+        positive(
+            &mut problems,
+            "pheromones.food_half_life",
+            self.pheromones.food_half_life.into(),
+        );
+        if !(0.0..1.0).contains(&self.pheromones.evaporation_floor) {
+            problems.push(format!(
+                "pheromones.evaporation_floor must be at least 0 and below 1, got {}",
+                self.pheromones.evaporation_floor
+            ));
+        }
+        for (name, value) in [
+            ("pheromones.nest_deposit", self.pheromones.nest_deposit),
+            ("pheromones.food_deposit", self.pheromones.food_deposit),
+        ] {
+            if !(value >= 0.0 && value.is_finite()) {
+                problems.push(format!("{name} must not be negative, got {value}"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    pub fn bounds(&self) -> WorldBounds {
+        WorldBounds::new(self.world.width, self.world.height).with_boundary(self.world.boundary)
+    }
+
+    // Nest position of the colony with the given index
+    pub fn nest_position(&self, colony: usize) -> Vec2 {
+        self.nest_position_within(&self.bounds(), colony)
+    }
+
+    // Nest position of the colony with the given index in a world of the
+    // given size, which a map may decide instead of `world`
+    pub fn nest_position_within(&self, bounds: &WorldBounds, colony: usize) -> Vec2 {
+        if let Some(position) = self.colonies.get(colony).and_then(|colony| colony.nest) {
+            return Vec2::from(position);
+        }
+
+        if self.colonies.len() <= 1 {
+            return bounds.center();
+        }
+        let angle = std::f32::consts::TAU * colony as f32 / self.colonies.len() as f32;
+        bounds.center() + Vec2::from_angle(angle) * bounds.width.min(bounds.height) / 4.
+    }
+}
+
+impl ObstacleShape {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            ObstacleShape::Rectangle { center, size } => {
+                let offset = (point - Vec2::from(*center)).abs();
+                offset.x <= size[0] / 2. && offset.y <= size[1] / 2.
+            }
+            ObstacleShape::Circle { center, radius } => {
+                point.distance(Vec2::from(*center)) <= *radius
+            }
+            ObstacleShape::Polygon { points } => {
+                // Even-odd rule: count the edges a ray to the right crosses
+                let mut inside = false;
+                for (index, [x1, y1]) in points.iter().enumerate() {
+                    let [x2, y2] = points[(index + 1) % points.len()];
+                    if (*y1 > point.y) != (y2 > point.y)
+                        && point.x < x1 + (point.y - y1) / (y2 - y1) * (x2 - x1)
+                    {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    // Smallest axis-aligned box around the shape, as (min, max) corners
+    pub fn bounding_box(&self) -> (Vec2, Vec2) {
+        match self {
+            ObstacleShape::Rectangle { center, size } => {
+                let half = Vec2::from(*size) / 2.;
+                (Vec2::from(*center) - half, Vec2::from(*center) + half)
+            }
+            ObstacleShape::Circle { center, radius } => (
+                Vec2::from(*center) - Vec2::splat(*radius),
+                Vec2::from(*center) + Vec2::splat(*radius),
+            ),
+            ObstacleShape::Polygon { points } => points.iter().map(|point| Vec2::from(*point)).fold(
+                (Vec2::splat(f32::INFINITY), Vec2::splat(f32::NEG_INFINITY)),
+                |(min, max), point| (min.min(point), max.max(point)),
+            ),
+        }
+    }
+}
+
+fn read_config(path: &Path) -> Result<String, ConfigError> {
+    std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// Applies a `section.key=value` assignment. The value is parsed as TOML and
+// falls back to a plain string, so `--set food.count=3` and
+// `--set simulation.seed=42` work without extra quoting.
+fn apply_override(table: &mut toml::Table, assignment: &str) -> Result<(), ConfigError> {
+    let (key, raw_value) = assignment
+        .split_once('=')
+        .ok_or_else(|| ConfigError::Override(format!("`{assignment}` is not of the form key=value")))?;
+    let value = toml::from_str::<toml::Table>(&format!("value = {raw_value}"))
+        .ok()
+        .and_then(|mut parsed| parsed.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw_value.to_string()));
+
+    let mut path: Vec<&str> = key.trim().split('.').collect();
+    let last = path.pop().filter(|last| !last.is_empty());
+    let Some(last) = last else {
+        return Err(ConfigError::Override(format!("`{assignment}` has an empty key")));
+    };
+
+    let mut current = table;
+    for section in path {
+        current = current
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| ConfigError::Override(format!("`{section}` in `{key}` is not a section")))?;
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+#[test]
+fn overrides_take_precedence_over_the_file() {
+    let config = SimConfig::from_toml(
+        "[ants]\ncount = 10\n",
+        "test",
+        &["ants.count=20".to_string(), "simulation.seed=7".to_string()],
+    )
+    .unwrap();
+
+    assert_eq!(config.ants.count, 20);
+    assert_eq!(config.simulation.seed, Some(7));
+    assert_eq!(config.food, FoodConfig::default());
+}
+
+#[test]
+fn validation_reports_every_problem() {
+    let error = SimConfig::from_toml(
+        "[ants]\nmin_lifetime = 50.0\nmax_lifetime = 10.0\n[pheromones]\nfood_half_life = 0.0\n",
+        "test",
+        &[],
+    )
+    .unwrap_err();
+
+    let ConfigError::Invalid(problems) = error else {
+        panic!("expected a validation error, got {error}");
+    };
+    assert_eq!(problems.len(), 2);
+}
+
+#[test]
+fn fixed_food_must_lie_in_the_world() {
+    let error = SimConfig::from_toml(
+        "[food.placement]\nstrategy = \"fixed\"\npositions = [[100.0, 50.0], [5000.0, 50.0]]\n",
+        "test",
+        &[],
+    )
+    .unwrap_err();
+
+    let ConfigError::Invalid(problems) = error else {
+        panic!("expected a validation error, got {error}");
+    };
+    assert_eq!(problems, ["food position [5000, 50] lies outside the world"]);
+}
+
+#[test]
+fn example_config_lists_the_defaults() {
+    let example = include_str!("../ants.example.toml");
+
+    let config = SimConfig::from_toml(example, "ants.example.toml", &[]).unwrap();
+
+    assert_eq!(config, SimConfig::default());
+}
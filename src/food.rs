@@ -1,43 +1,232 @@
 use bevy::prelude::*;
 use crate::components::position::Position;
-use bevy::window::PrimaryWindow;
 use crate::components::food::Food;
+use crate::config::{FoodPlacement, SimConfig};
+use crate::map::{ArenaMap, nest_position};
+use crate::obstacles::setup_obstacles;
+use crate::resources::obstacle_map::ObstacleMap;
+use crate::resources::sim_rng::{RngStream, SimRng};
+use crate::resources::world_bounds::WorldBounds;
+use crate::utils::geometry::random_point_in_circle;
+use rand::Rng;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+// How often a random food spot is redrawn before one inside an obstacle is kept
+const MAX_PLACEMENT_ATTEMPTS: usize = 100;
 
 pub struct FoodPlugin;
 
 impl Plugin for FoodPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(Startup, setup_food);
+            .init_resource::<FoodSpawner>()
+            .add_systems(Startup, setup_food.after(setup_obstacles))
+            .add_systems(FixedPostUpdate, respawn_depleted_food);
+    }
+}
+
+// Keeps track of which placement slot each food source occupies, so depleted
+// sources can be replaced according to the placement strategy
+#[derive(Resource, Default)]
+pub struct FoodSpawner {
+    // Patches from the arena map, which replace the placement strategy
+    map_sources: Vec<(Vec2, u32)>,
+    // Centre of the `ring` placement
+    nest: Vec2,
+    cluster_centers: Vec<Vec2>,
+    slots: HashMap<Entity, usize>,
+    pending: Vec<(usize, Timer)>,
+}
+
+impl FoodSpawner {
+    // World position for the source in `slot`
+    fn position(
+        &self,
+        slot: usize,
+        placement: &FoodPlacement,
+        count: usize,
+        nest: Vec2,
+        bounds: &WorldBounds,
+        rng: &mut impl Rng,
+    ) -> Vec2 {
+        let position = match placement {
+            FoodPlacement::Line => {
+                let spacing = bounds.width / 2. / (count as f32 + 1.0);
+                Vec2::new(bounds.width / 4. + spacing * (slot + 1) as f32, bounds.height / 2.0)
+            }
+            FoodPlacement::Fixed { positions } => Vec2::from(positions[slot]),
+            FoodPlacement::Uniform => Vec2::new(
+                rng.gen_range(0.0..bounds.width),
+                rng.gen_range(0.0..bounds.height),
+            ),
+            FoodPlacement::Clusters { radius, .. } => {
+                let center = self.cluster_centers[slot % self.cluster_centers.len()];
+                center + random_point_in_circle(rng, *radius)
+            }
+            FoodPlacement::Ring { radius } => {
+                let angle = TAU * slot as f32 / count as f32;
+                nest + Vec2::from_angle(angle) * *radius
+            }
+        };
+
+        // Keep every source inside the wrapped arena
+        Vec2::new(
+            position.x.rem_euclid(bounds.width),
+            position.y.rem_euclid(bounds.height),
+        )
     }
 }
 
+// Position for the source in `slot`, avoiding obstacles. Random spots are drawn
+// again while they land inside one.
+fn free_position(
+    spawner: &FoodSpawner,
+    slot: usize,
+    config: &SimConfig,
+    bounds: &WorldBounds,
+    obstacles: &ObstacleMap,
+    rng: &mut impl Rng,
+) -> Vec2 {
+    if let Some((position, _)) = spawner.map_sources.get(slot) {
+        return *position;
+    }
+    let placement = &config.food.placement;
+    let count = slot_count(spawner, config);
+    let nest = spawner.nest;
 
+    let mut position = spawner.position(slot, placement, count, nest, bounds, rng);
+    for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+        if !obstacles.is_blocked(position) {
+            break;
+        }
+        position = spawner.position(slot, placement, count, nest, bounds, rng);
+    }
+    position
+}
+
+fn slot_count(spawner: &FoodSpawner, config: &SimConfig) -> usize {
+    if !spawner.map_sources.is_empty() {
+        return spawner.map_sources.len();
+    }
+    match &config.food.placement {
+        FoodPlacement::Fixed { positions } => positions.len(),
+        _ => config.food.count,
+    }
+}
+
+// Food in the source in `slot` when it is (re)spawned
+fn slot_amount(spawner: &FoodSpawner, slot: usize, config: &SimConfig) -> u32 {
+    spawner
+        .map_sources
+        .get(slot)
+        .map_or(config.food.amount, |(_, amount)| *amount)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn setup_food(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut spawner: ResMut<FoodSpawner>,
+    bounds: Res<WorldBounds>,
+    obstacles: Res<ObstacleMap>,
+    map: Option<Res<ArenaMap>>,
+    config: Res<SimConfig>,
+    sim_rng: Res<SimRng>,
 ) {
-    let window = window_query.get_single().unwrap();
-    let width = window.width();
-    let height = window.height();
-
-    let circle_material = materials.add(Color::srgb(0., 0., 0.));
-    let circle_mesh = meshes.add(Circle::new(5.0));
-    
-    let num_foods = 5;
-    let spacing = width / 2. / (num_foods as f32 + 1.0);
-    let y_position = height / 2.0;
-
-    for i in 1..=num_foods {
-        let x_position = width / 4. + spacing * i as f32;
-        commands.spawn((
-            Food,
-            Position { position: Vec2::new(x_position, y_position) },
-            Mesh2d(circle_mesh.clone()),
-            MeshMaterial2d(circle_material.clone()),
-            Transform::from_xyz(0., 0., 0.0),
-        ));
+    let mut rng = sim_rng.stream(RngStream::Food);
+    spawner.nest = nest_position(&config, map.as_deref(), 0);
+    if let Some(map) = &map {
+        spawner.map_sources = map.food.clone();
+    }
+
+    if let FoodPlacement::Clusters { clusters, .. } = config.food.placement {
+        spawner.cluster_centers = (0..clusters)
+            .map(|_| {
+                Vec2::new(
+                    rng.gen_range(0.0..bounds.width),
+                    rng.gen_range(0.0..bounds.height),
+                )
+            })
+            .collect();
+    }
+
+    for slot in 0..slot_count(&spawner, &config) {
+        let position = free_position(&spawner, slot, &config, &bounds, &obstacles, &mut rng);
+        let amount = slot_amount(&spawner, slot, &config);
+        let entity = commands
+            .spawn((Food::new(amount), Position { position }))
+            .id();
+        spawner.slots.insert(entity, slot);
+    }
+}
+
+// Replaces depleted sources after `food.respawn_delay` simulated seconds
+#[allow(clippy::too_many_arguments)]
+fn respawn_depleted_food(
+    mut commands: Commands,
+    mut spawner: ResMut<FoodSpawner>,
+    mut removed: RemovedComponents<Food>,
+    bounds: Res<WorldBounds>,
+    obstacles: Res<ObstacleMap>,
+    config: Res<SimConfig>,
+    sim_rng: Res<SimRng>,
+    time: Res<Time>,
+) {
+    for entity in removed.read() {
+        let Some(slot) = spawner.slots.remove(&entity) else {
+            continue;
+        };
+        if let Some(delay) = config.food.respawn_delay {
+            let timer = Timer::new(Duration::from_secs_f32(delay), TimerMode::Once);
+            spawner.pending.push((slot, timer));
+        }
+    }
+
+    for (_, timer) in spawner.pending.iter_mut() {
+        timer.tick(time.delta());
+    }
+
+    let mut rng = sim_rng.stream(RngStream::Food);
+    let pending = std::mem::take(&mut spawner.pending);
+    for (slot, timer) in pending {
+        if !timer.finished() {
+            spawner.pending.push((slot, timer));
+            continue;
+        }
+
+        let position = free_position(&spawner, slot, &config, &bounds, &obstacles, &mut rng);
+        let amount = slot_amount(&spawner, slot, &config);
+        let entity = commands
+            .spawn((Food::new(amount), Position { position }))
+            .id();
+        spawner.slots.insert(entity, slot);
+    }
+}
+
+#[test]
+fn depleted_food_respawns_in_its_slot() {
+    let mut config = SimConfig::default();
+    config.ants.count = 0;
+    config.food.placement = FoodPlacement::Fixed {
+        positions: vec![[100., 50.]],
+    };
+    config.food.respawn_delay = Some(0.5);
+    let mut app = crate::headless::headless_app(config);
+    app.update();
+
+    let mut food_query = app.world_mut().query_filtered::<Entity, With<Food>>();
+    let food = food_query.single(app.world());
+    app.world_mut().despawn(food);
+    for _ in 0..20 {
+        app.update();
+    }
+    assert_eq!(food_query.iter(app.world()).count(), 0);
+
+    for _ in 0..20 {
+        app.update();
     }
+    let mut positions = app.world_mut().query_filtered::<&Position, With<Food>>();
+    let respawned: Vec<Vec2> = positions.iter(app.world()).map(|p| p.position).collect();
+    assert_eq!(respawned, [Vec2::new(100., 50.)]);
 }
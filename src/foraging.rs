@@ -0,0 +1,385 @@
+use crate::components::ant_state::AntState;
+use crate::components::colony_id::ColonyId;
+use crate::components::hazard::Hazard;
+use crate::components::role::Role;
+use crate::config::{SensingModel, SimConfig};
+use crate::pheromones::{Alarm, PheromoneGrid, PheromoneGridTrait, PheromoneGrids};
+use crate::resources::obstacle_map::ObstacleMap;
+use crate::resources::world_bounds::WorldBounds;
+use crate::utils::geometry::*;
+use bevy::prelude::*;
+use rand::Rng;
+use rand::rngs::StdRng;
+
+// Decides where an ant heads each tick and whether it lays its trail. The
+// simulation still moves the ant, keeps it out of walls and handles the world
+// edge; a strategy only steers. Headings need not be normalised, and a zero
+// one leaves the ant facing the way it was. Assign a strategy to a colony
+// through `ForagingStrategies` or to a single ant with the `Strategy`
+// component.
+pub trait ForagingStrategy: Send + Sync + 'static {
+    // `rng` is the ant's own movement stream, so runs stay reproducible
+    fn decide(&self, ant: &AntContext, rng: &mut StdRng) -> Decision;
+}
+
+// What a strategy wants an ant to do this tick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    // Direction the ant faces from now on; zero keeps the current one
+    pub heading: Vec2,
+    // Direction of this tick's step, usually the heading with some jitter
+    pub step: Vec2,
+    // Whether the ant lays its trail pheromone, if its state allows it
+    pub deposit: bool,
+    // State the ant moves into; `None` keeps the current one
+    pub state: Option<AntState>,
+}
+
+impl Decision {
+    // Walk straight along `heading`, laying the trail as usual
+    pub fn heading(heading: Vec2) -> Self {
+        Self {
+            heading,
+            step: heading,
+            deposit: true,
+            state: None,
+        }
+    }
+}
+
+// Everything an ant knows when its strategy decides
+pub struct AntContext<'a> {
+    pub position: Vec2,
+    pub heading: Vec2,
+    pub colony: ColonyId,
+    pub role: Role,
+    pub state: AntState,
+    pub carrying_food: bool,
+    // Distance walked since the ant last touched its nest or a food source
+    pub travelled: f32,
+    // Simulated seconds this tick covers
+    pub delta: f32,
+    pub config: &'a SimConfig,
+    pub(crate) senses: Senses<'a>,
+    pub(crate) landmarks: &'a Landmarks,
+}
+
+impl AntContext<'_> {
+    // Trail and alarm level at a world position, if the ant can sense there.
+    // The trail is the one leading to the ant's current goal: food trails
+    // while searching and nest trails while carrying food.
+    pub fn read(&self, position: Vec2) -> Option<Reading> {
+        self.senses.read(position)
+    }
+
+    // Level of the ant's colony's layer of any kind registered with
+    // `add_pheromone`, if the ant can sense there
+    pub fn sample<K: Send + Sync + 'static>(&self, position: Vec2) -> Option<f32> {
+        self.senses.sample::<K>(position)
+    }
+
+    // Reads every degree of the view cone at every whole distance, and heads
+    // for the strongest reading
+    pub fn sense_cone(&self) -> Sensed {
+        let sensing = &self.config.sensing;
+        let mut sensed = Sensed::nothing(self.heading);
+        let view_angle = sensing.view_angle as i32;
+        for angle in -view_angle..=view_angle {
+            let rotated_direction = rotate_vector(self.heading, angle as f32);
+            for dist in 1..=sensing.view_radius {
+                let Some(reading) = self.read(self.position + rotated_direction * dist as f32)
+                else {
+                    break;
+                };
+                sensed.add_reading(reading, rotated_direction);
+            }
+        }
+        sensed
+    }
+
+    // Reads one probe ahead and one to either side, and turns toward the
+    // stronger side the more the two differ
+    pub fn sense_probes(&self) -> Sensed {
+        let sensing = &self.config.sensing;
+        let mut sensed = Sensed::nothing(self.heading);
+        let angles = [-sensing.probe_angle, 0.0, sensing.probe_angle];
+        let [right, centre, left] = angles.map(|angle| {
+            let direction = rotate_vector(self.heading, angle);
+            let probe = self.position + direction * sensing.probe_distance;
+            let reading = if self.senses.obstacles.is_path_blocked(self.position, probe) {
+                None
+            } else {
+                self.read(probe)
+            };
+            let reading = reading.unwrap_or_default();
+            sensed.add_reading(reading, direction);
+            reading.trail
+        });
+
+        // Keep going straight while the centre probe reads the most
+        sensed.trail_direction = self.heading;
+        if centre < left.max(right) {
+            let contrast = (left - right) / (left + right);
+            sensed.trail_direction =
+                rotate_vector(self.heading, contrast * sensing.turn_rate * self.delta);
+        }
+        sensed
+    }
+
+    // Food sources with food left within `radius` of the ant
+    pub fn nearby_food(&self, radius: f32) -> impl Iterator<Item = Vec2> + '_ {
+        self.landmarks
+            .food
+            .iter()
+            .copied()
+            .filter(move |food| food.distance(self.position) <= radius)
+    }
+
+    // Hazards whose edge is within `radius` of the ant
+    pub fn nearby_hazards(&self, radius: f32) -> impl Iterator<Item = (Vec2, Hazard)> + '_ {
+        self.landmarks
+            .hazards
+            .iter()
+            .copied()
+            .filter(move |(center, hazard)| {
+                center.distance(self.position) <= radius + hazard.radius
+            })
+    }
+
+    // The closest nest of the ant's own colony
+    pub fn nest(&self) -> Option<Vec2> {
+        self.landmarks
+            .nests
+            .iter()
+            .filter(|(colony, _)| *colony == self.colony)
+            .map(|(_, nest)| *nest)
+            .min_by(|a, b| {
+                a.distance_squared(self.position)
+                    .total_cmp(&b.distance_squared(self.position))
+            })
+    }
+}
+
+// Pheromone levels at one spot
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Reading {
+    pub trail: f32,
+    pub alarm: f32,
+}
+
+// The strongest trail and alarm an ant sensed this tick, and where to head
+// for each
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sensed {
+    pub trail: f32,
+    pub trail_direction: Vec2,
+    pub alarm: f32,
+    pub alarm_direction: Vec2,
+}
+
+impl Sensed {
+    fn nothing(heading: Vec2) -> Self {
+        Self {
+            trail: 0.0,
+            trail_direction: heading,
+            alarm: 0.0,
+            alarm_direction: heading,
+        }
+    }
+
+    fn add_reading(&mut self, reading: Reading, direction: Vec2) {
+        if reading.trail > self.trail {
+            self.trail = reading.trail;
+            self.trail_direction = direction;
+        }
+        if reading.alarm > self.alarm {
+            self.alarm = reading.alarm;
+            self.alarm_direction = direction;
+        }
+    }
+}
+
+// The pheromone layers one ant can sense
+pub(crate) struct Senses<'a> {
+    pub bounds: &'a WorldBounds,
+    pub obstacles: &'a ObstacleMap,
+    pub trail: &'a dyn PheromoneGridTrait,
+    // Missing while the colony has no alarm anywhere
+    pub alarm: Option<&'a PheromoneGrid<Alarm>>,
+    pub grids: &'a PheromoneGrids<'a, 'a>,
+    pub colony: ColonyId,
+}
+
+impl Senses<'_> {
+    // Ants cannot sense past the edge of the world or through obstacles
+    fn sensed_position(&self, position: Vec2) -> Option<Vec2> {
+        let position = self.bounds.lookup(position)?;
+        (!self.obstacles.is_blocked(position)).then_some(position)
+    }
+
+    fn read(&self, position: Vec2) -> Option<Reading> {
+        let position = self.sensed_position(position)?;
+        // Only the ant's own colony's trails guide it
+        let trail = self.trail.sample(self.colony, position);
+        let alarm = self
+            .alarm
+            .map_or(0.0, |alarm| alarm.sample(self.colony, position));
+        Some(Reading { trail, alarm })
+    }
+
+    fn sample<K: Send + Sync + 'static>(&self, position: Vec2) -> Option<f32> {
+        let position = self.sensed_position(position)?;
+        let grid = self.grids.get::<K>().unwrap_or_else(|| {
+            panic!("pheromone kind {} is not registered", std::any::type_name::<K>())
+        });
+        Some(grid.sample(self.colony, position))
+    }
+}
+
+// Food sources, nests and hazards, gathered once per tick for every ant
+#[derive(Default)]
+pub(crate) struct Landmarks {
+    pub food: Vec<Vec2>,
+    pub nests: Vec<(ColonyId, Vec2)>,
+    pub hazards: Vec<(Vec2, Hazard)>,
+}
+
+// The built-in behaviour: sense with the configured model, flee or rally to
+// alarms by role, follow the strongest trail, and wander when there is none
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PheromoneFollowing;
+
+impl ForagingStrategy for PheromoneFollowing {
+    fn decide(&self, ant: &AntContext, rng: &mut StdRng) -> Decision {
+        let sensed = match ant.config.sensing.model {
+            SensingModel::Cone => ant.sense_cone(),
+            SensingModel::Probes => ant.sense_probes(),
+        };
+
+        // Searching ants follow trails; laden ones just head home
+        let searching = |trail_found| match (ant.carrying_food, trail_found) {
+            (true, _) => AntState::ReturningWithFood,
+            (false, true) => AntState::FollowingTrail,
+            (false, false) => AntState::Exploring,
+        };
+        let (heading, state) = if sensed.alarm > ant.config.alarm.threshold {
+            // Danger outweighs any trail: defenders rally to it, workers flee
+            match ant.role {
+                Role::Defender => (sensed.alarm_direction.normalize(), ant.state),
+                Role::Worker => (-sensed.alarm_direction.normalize(), AntState::Fleeing),
+            }
+        } else if sensed.trail == 0.0 {
+            // If no pheromone is found, move randomly within the view angle
+            let view_angle = ant.config.sensing.view_angle; // in degrees
+            let random_angle: f32 = rng.gen_range((-view_angle / 2.)..=view_angle / 2.);
+            let heading = rotate_vector(ant.heading, random_angle).normalize();
+            (heading, searching(false))
+        } else {
+            (sensed.trail_direction.normalize(), searching(true))
+        };
+        // Add some randomness to the step
+        let random_offset: Vec2 = random_normalized_direction(rng) * rng.gen_range(0.0..0.8);
+        Decision {
+            heading,
+            step: (heading + random_offset).normalize(),
+            deposit: true,
+            state: Some(state),
+        }
+    }
+}
+
+#[test]
+fn ants_follow_their_own_or_their_colonys_strategy() {
+    use crate::components::position::Position;
+    use crate::components::strategy::Strategy;
+    use crate::headless::{empty_world, spawn_ant};
+    use crate::pheromones::Nest;
+    use crate::resources::foraging_strategies::ForagingStrategies;
+
+    // Walks one way and never lays a trail
+    struct Beeline(Vec2);
+    impl ForagingStrategy for Beeline {
+        fn decide(&self, _: &AntContext, _: &mut StdRng) -> Decision {
+            Decision {
+                deposit: false,
+                ..Decision::heading(self.0)
+            }
+        }
+    }
+
+    let mut app = empty_world(|_| {});
+    app.world_mut()
+        .resource_mut::<ForagingStrategies>()
+        .set_colony(ColonyId(0), Beeline(Vec2::X));
+
+    let start = Vec2::new(100., 40.);
+    let colony_ant = spawn_ant(&mut app, start).id();
+    let own_ant = spawn_ant(&mut app, start)
+        .insert(Strategy::new(Beeline(Vec2::Y)))
+        .id();
+    app.update();
+
+    let step = app.world().resource::<SimConfig>().ants.speed / 60.;
+    let moved = |ant| app.world().get::<Position>(ant).unwrap().position - start;
+    assert!(
+        moved(colony_ant).abs_diff_eq(Vec2::X * step, 1e-4),
+        "{}",
+        moved(colony_ant)
+    );
+    assert!(
+        moved(own_ant).abs_diff_eq(Vec2::Y * step, 1e-4),
+        "{}",
+        moved(own_ant)
+    );
+    let grid = app.world().resource::<PheromoneGrid<Nest>>();
+    assert!(grid.layers[0].is_clear());
+}
+
+#[test]
+fn strategies_sense_registered_pheromone_kinds() {
+    use crate::components::position::Position;
+    use crate::headless::{headless_app, spawn_ant};
+    use crate::pheromones::{AddPheromone, PheromoneColor, PheromoneSpec, Trail};
+    use crate::resources::foraging_strategies::ForagingStrategies;
+
+    struct Repellent;
+
+    // Backs off wherever there is repellent and walks on otherwise
+    struct Shy;
+    impl ForagingStrategy for Shy {
+        fn decide(&self, ant: &AntContext, _: &mut StdRng) -> Decision {
+            let repelled = ant.sample::<Repellent>(ant.position).unwrap_or(0.0) > 0.0;
+            Decision::heading(if repelled { -Vec2::X } else { Vec2::X })
+        }
+    }
+
+    let mut config = SimConfig::default();
+    config.world.width = 200.;
+    config.world.height = 200.;
+    config.ants.count = 0;
+    let mut app = headless_app(config);
+    app.add_pheromone::<Repellent>(PheromoneSpec {
+        color: PheromoneColor {
+            r: 200,
+            g: 0,
+            b: 0,
+            a: 255,
+        },
+        increment: 0.0,
+        half_life: 1.0,
+        trail: Trail::Events,
+    });
+    app.world_mut()
+        .resource_mut::<ForagingStrategies>()
+        .set_colony(ColonyId(0), Shy);
+    app.update();
+
+    let shy = spawn_ant(&mut app, Vec2::new(50., 40.)).id();
+    let bold = spawn_ant(&mut app, Vec2::new(150., 40.)).id();
+    app.world_mut().resource_mut::<PheromoneGrid<Repellent>>().layers[0].set(50, 40, 1.0);
+    app.update();
+
+    let x = |ant| app.world().get::<Position>(ant).unwrap().position.x;
+    assert!(x(shy) < 50., "{}", x(shy));
+    assert!(x(bold) > 150., "{}", x(bold));
+}
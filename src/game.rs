@@ -1,37 +1,63 @@
+use crate::config::SimConfig;
+use crate::map::ArenaMap;
+use crate::resources::sim_rng::SimRng;
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
 
-pub struct GamePlugin;
+// The simulation itself: ants, food, obstacles, hazards and pheromones,
+// without any rendering. It is configured by the `SimConfig` resource present
+// when the plugin is added (defaults otherwise). An `ArenaMap` resource present
+// by then is used as the arena; otherwise the plugin loads `world.map` itself
+// and panics if it cannot. All simulation systems run in `FixedUpdate`, so the
+// colony behaves the same at any frame rate.
+pub struct SimulationPlugin;
 
-impl Plugin for GamePlugin {
+impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
+        let config = app
+            .world()
+            .get_resource::<SimConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let sim_rng = config.simulation.seed.map(SimRng::new).unwrap_or_default();
+        let map = app.world_mut().remove_resource::<ArenaMap>().or_else(|| {
+            ArenaMap::from_config(&config).map(|map| map.unwrap_or_else(|error| panic!("{error}")))
+        });
+        // A map decides the size of the world and of every grid over it
+        let bounds = match &map {
+            Some(map) => map.bounds().with_boundary(config.world.boundary),
+            None => config.bounds(),
+        };
+        if let Some(map) = map {
+            app.insert_resource(map);
+        }
+
         app
+            .insert_resource(bounds)
+            .insert_resource(sim_rng)
+            .insert_resource(Time::<Fixed>::from_hz(config.simulation.tick_rate))
+            .insert_resource(config)
+            .init_resource::<crate::resources::colony_stats::ColonyStats>()
+            .add_systems(FixedFirst, crate::resources::sim_rng::advance_sim_rng)
             .add_plugins(
                 (
                     crate::ant::AntPlugin,
                     crate::food::FoodPlugin,
+                    crate::hazards::HazardPlugin,
+                    crate::nest::NestPlugin,
+                    crate::obstacles::ObstaclePlugin,
                     crate::pheromones::PheromonePlugin,
                 )
-            )
-            .add_systems(Startup, setup_camera);
+            );
     }
 }
 
-fn setup_camera(
-    mut commands: Commands,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-) {
-    let window = window_query.get_single().unwrap();
-    let width = window.width();
-    let height = window.height();
-    commands.spawn((
-        Camera2d, // New way to spawn a 2D camera
-        Camera {
-            order: 0, // Default camera order
-            ..default()
-        },
-        Projection::Orthographic(OrthographicProjection::default_2d()),
-        Transform::from_xyz(width / 2., height / 2., 0.0),
-    ));
-}
+// The simulation together with the viewer used by the `ants` binary
+#[cfg(feature = "viewer")]
+pub struct GamePlugin;
 
+#[cfg(feature = "viewer")]
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((SimulationPlugin, crate::viewer::ViewerPlugin));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::components::ant::Ant;
+use crate::components::colony_id::ColonyId;
+use crate::components::direction::Direction;
+use crate::components::dying::Dying;
+use crate::components::hazard::Hazard;
+use crate::components::position::Position;
+use crate::components::previous_position::PreviousPosition;
+use crate::config::{HazardKind, SimConfig};
+use crate::pheromones::{Alarm, DepositPheromone, PheromoneSystems};
+use crate::resources::obstacle_map::ObstacleMap;
+use crate::resources::sim_rng::{RngStream, SimRng};
+use crate::resources::world_bounds::{EdgeCrossing, WorldBounds};
+use crate::utils::geometry::rotate_vector;
+use bevy::prelude::*;
+use rand::Rng;
+
+// Dangers from `[[hazards]]`. Ants inside one lay alarm pheromone for their
+// colony, and poison zones and predators kill them.
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_hazards).add_systems(
+            FixedUpdate,
+            // Alarms land on the grid in the tick they are raised
+            (move_predators, hazard_system)
+                .chain()
+                .before(PheromoneSystems),
+        );
+    }
+}
+
+fn setup_hazards(mut commands: Commands, config: Res<SimConfig>) {
+    for hazard in &config.hazards {
+        commands.spawn(hazard_bundle(
+            hazard.kind,
+            Vec2::from(hazard.center),
+            hazard.radius,
+        ));
+    }
+}
+
+// Components of a hazard of `kind` centred at `position`
+pub fn hazard_bundle(kind: HazardKind, position: Vec2, radius: f32) -> impl Bundle {
+    (
+        Hazard { kind, radius },
+        Position { position },
+        PreviousPosition { position },
+        // Only predators move
+        Direction { direction: Vec2::X },
+    )
+}
+
+// Predators wander the world, turning a little every tick
+fn move_predators(
+    mut predators: Query<(Entity, &Hazard, &mut Position, &mut Direction)>,
+    bounds: Res<WorldBounds>,
+    obstacles: Res<ObstacleMap>,
+    sim_rng: Res<SimRng>,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+) {
+    let step = config.alarm.predator_speed * time.delta_secs();
+    for (entity, hazard, mut position, mut direction) in predators.iter_mut() {
+        if hazard.kind != HazardKind::Predator {
+            continue;
+        }
+        let mut rng = sim_rng.entity_stream(RngStream::Hazard, entity);
+        let turn: f32 = rng.gen_range(-15.0..=15.0);
+        direction.direction = rotate_vector(direction.direction, turn).normalize();
+
+        let next_position = position.position + direction.direction * step;
+        match bounds.cross(next_position) {
+            EdgeCrossing::Inside(wrapped)
+                if !obstacles.is_path_blocked(position.position, next_position) =>
+            {
+                position.position = wrapped;
+            }
+            EdgeCrossing::Reflected {
+                position: reflected,
+                flip,
+            } if !obstacles.is_blocked(reflected) => {
+                direction.direction = Vec2::select(flip, -direction.direction, direction.direction);
+                position.position = reflected;
+            }
+            // Predators are never lost; they turn back from walls and the void
+            _ => direction.direction = -direction.direction,
+        }
+    }
+}
+
+// Every ant inside a hazard raises the alarm where it stands, and may die if
+// the hazard is lethal
+fn hazard_system(
+    mut commands: Commands,
+    hazards: Query<(&Hazard, &Position)>,
+    ants: Query<(Entity, &Position, &ColonyId), With<Ant>>,
+    mut alarms: EventWriter<DepositPheromone<Alarm>>,
+    sim_rng: Res<SimRng>,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+) {
+    if hazards.is_empty() {
+        return;
+    }
+    let delta = time.delta_secs();
+    let deposit = config.alarm.deposit * delta;
+    let death_chance = (config.alarm.lethality * delta).min(1.0);
+
+    for (ant, position, colony) in ants.iter() {
+        let mut inside = false;
+        let mut lethal = false;
+        for (hazard, hazard_position) in hazards.iter() {
+            if position.position.distance(hazard_position.position) <= hazard.radius {
+                inside = true;
+                lethal |= hazard.kind.is_lethal();
+            }
+        }
+        if !inside {
+            continue;
+        }
+
+        alarms.send(DepositPheromone::new(position.position, *colony, deposit));
+        let mut rng = sim_rng.entity_stream(RngStream::Hazard, ant);
+        if lethal && rng.gen_bool(death_chance as f64) {
+            commands.entity(ant).try_insert(Dying);
+        }
+    }
+}
+
+#[test]
+fn poison_kills_ants_and_raises_the_alarm() {
+    use crate::config::HazardConfig;
+    use crate::pheromones::PheromoneGrid;
+    use crate::resources::colony_stats::ColonyStats;
+
+    let mut config = SimConfig::default();
+    config.world.width = 200.;
+    config.world.height = 200.;
+    config.simulation.seed = Some(5);
+    config.ants.count = 100;
+    // Right on top of the nest, where every ant starts
+    config.hazards = vec![HazardConfig {
+        kind: HazardKind::Poison,
+        center: [100., 100.],
+        radius: 30.,
+    }];
+    let mut app = crate::headless::headless_app(config);
+    for _ in 0..30 {
+        app.update();
+    }
+
+    let stats = app.world().resource::<ColonyStats>();
+    assert!(stats.colonies[0].deaths > 0);
+    let alarm = &app.world().resource::<PheromoneGrid<Alarm>>().layers[0];
+    assert!(alarm.get(100, 100) > 0.1);
+    assert_eq!(alarm.get(10, 10), 0.0);
+}
@@ -0,0 +1,189 @@
+use crate::config::SimConfig;
+use crate::map::ArenaMap;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::time::Instant;
+
+// Builds an app that runs the simulation without a window or renderer.
+// Every update advances time by exactly one fixed timestep, so runs with the
+// same seed are reproducible however fast the CPU is.
+pub fn headless_app(config: SimConfig) -> App {
+    headless_app_with_map(config, None)
+}
+
+// Like `headless_app`, on an arena map that has already been loaded
+pub fn headless_app_with_map(config: SimConfig, map: Option<ArenaMap>) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins).insert_resource(config);
+    if let Some(map) = map {
+        app.insert_resource(map);
+    }
+    app.add_plugins(crate::game::SimulationPlugin);
+
+    let timestep = app.world().resource::<Time<Fixed>>().timestep();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+    // Virtual time clamps each step to 250 ms by default, which would cut the
+    // steps of tick rates below 4 Hz short
+    let mut virtual_time = app.world_mut().resource_mut::<Time<Virtual>>();
+    let max_delta = virtual_time.max_delta().max(timestep);
+    virtual_time.set_max_delta(max_delta);
+    app
+}
+
+// Steps the simulation `ticks` times as fast as possible, then returns
+pub fn run_headless(config: SimConfig, map: Option<ArenaMap>, ticks: u64) {
+    let mut app = headless_app_with_map(config, map);
+    app.finish();
+    app.cleanup();
+    // The first update only runs Startup; time starts advancing after it
+    app.update();
+
+    let start = Instant::now();
+    for _ in 0..ticks {
+        app.update();
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+
+    println!(
+        "Simulated {} ticks in {:.2}s ({:.1} ticks/s)",
+        ticks,
+        elapsed,
+        ticks as f64 / elapsed.max(f64::EPSILON)
+    );
+
+    let stats = app.world().resource::<crate::resources::colony_stats::ColonyStats>();
+    for (colony, stat) in stats.colonies.iter().enumerate() {
+        println!(
+            "Colony {}: {} ants, {:.1} food stored, {} delivered, {} births, {} deaths",
+            colony, stat.population, stat.food_store, stat.food_delivered, stat.births, stat.deaths
+        );
+    }
+}
+
+#[test]
+fn headless_app_runs_without_a_window() {
+    let mut app = headless_app(test_config(200., 100., 0));
+    app.update();
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let sim_rng = app.world().resource::<crate::resources::sim_rng::SimRng>();
+    assert_eq!(sim_rng.tick(), 3);
+
+    let grid = app
+        .world()
+        .resource::<crate::pheromones::PheromoneGrid<crate::pheromones::Nest>>();
+    assert_eq!((grid.width, grid.height), (200, 100));
+}
+
+#[test]
+fn slow_tick_rates_still_advance_one_tick_per_update() {
+    let mut config = test_config(200., 100., 0);
+    config.simulation.tick_rate = 2.0;
+    let mut app = headless_app(config);
+    app.update();
+
+    for _ in 0..3 {
+        app.update();
+    }
+
+    let sim_rng = app.world().resource::<crate::resources::sim_rng::SimRng>();
+    assert_eq!(sim_rng.tick(), 3);
+}
+
+#[test]
+fn each_colony_gets_its_own_pheromone_layers() {
+    let mut config = test_config(200., 100., 0);
+    config.colonies = vec![Default::default(); 3];
+    let mut app = headless_app(config);
+    app.update();
+
+    let grid = app
+        .world()
+        .resource::<crate::pheromones::PheromoneGrid<crate::pheromones::Food>>();
+    assert_eq!(grid.layers.len(), 3);
+    let mut nests = app
+        .world_mut()
+        .query::<&crate::components::colony_id::ColonyId>();
+    assert_eq!(nests.iter(app.world()).count(), 3 * (1 + 5000));
+}
+
+#[test]
+fn same_seed_produces_identical_simulation_state() {
+    let run = |seed| {
+        let mut app = headless_app(test_config(300., 200., seed));
+        for _ in 0..30 {
+            app.update();
+        }
+        simulation_snapshot(app.world_mut())
+    };
+
+    assert_eq!(run(7), run(7));
+    assert_ne!(run(7), run(8));
+}
+
+#[cfg(test)]
+fn test_config(width: f32, height: f32, seed: u64) -> SimConfig {
+    let mut config = SimConfig::default();
+    config.world.width = width;
+    config.world.height = height;
+    config.simulation.seed = Some(seed);
+    config
+}
+
+// A started 200×200 world without any ants, for tests that place their own
+#[cfg(test)]
+pub(crate) fn empty_world(configure: impl FnOnce(&mut SimConfig)) -> App {
+    let mut config = SimConfig::default();
+    config.world.width = 200.;
+    config.world.height = 200.;
+    config.ants.count = 0;
+    configure(&mut config);
+    let mut app = headless_app(config);
+    app.update();
+    app
+}
+
+// Spawns one ant of the first colony into a test world
+#[cfg(test)]
+pub(crate) fn spawn_ant(app: &mut App, position: Vec2) -> EntityWorldMut<'_> {
+    use crate::components::colony_id::ColonyId;
+    use rand::SeedableRng;
+
+    let config = app.world().resource::<SimConfig>().clone();
+    let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    app.world_mut()
+        .spawn(crate::ant::ant_bundle(position, ColonyId(0), &config, &mut rng))
+}
+
+// Bit patterns of every position and pheromone cell, ordered by entity
+#[cfg(test)]
+fn simulation_snapshot(world: &mut World) -> Vec<u64> {
+    use crate::components::position::Position;
+    use crate::pheromones::PheromoneRegistry;
+
+    let mut positions: Vec<_> = world
+        .query::<(Entity, &Position)>()
+        .iter(world)
+        .map(|(entity, position)| (entity.to_bits(), position.position))
+        .collect();
+    positions.sort_unstable_by_key(|(entity, _)| *entity);
+
+    let mut snapshot: Vec<u64> = positions
+        .iter()
+        .flat_map(|(entity, position)| {
+            [*entity, position.x.to_bits() as u64, position.y.to_bits() as u64]
+        })
+        .collect();
+    for kind in world.resource::<PheromoneRegistry>().kinds() {
+        snapshot.extend(
+            kind.layers(world)
+                .iter()
+                .flat_map(|layer| layer.values())
+                .map(|value| value.to_bits() as u64),
+        );
+    }
+    snapshot
+}
@@ -0,0 +1,161 @@
+use crate::config::{PheromoneConfig, SimConfig};
+use bevy::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// How often the config file is checked for changes, in real time
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Watches the config file and applies tunable parameters while the simulation
+// runs. Only values that systems read every tick are applied; anything that
+// shapes the world at startup (sizes, counts, seed) is reported and ignored.
+pub struct HotReloadPlugin {
+    pub path: PathBuf,
+    // The same command line overrides the config was loaded with
+    pub overrides: Vec<String>,
+}
+
+#[derive(Resource)]
+struct ConfigWatcher {
+    path: PathBuf,
+    overrides: Vec<String>,
+    last_modified: Option<SystemTime>,
+    timer: Timer,
+}
+
+impl Plugin for HotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ConfigWatcher {
+            path: self.path.clone(),
+            overrides: self.overrides.clone(),
+            last_modified: modified_time(&self.path),
+            timer: Timer::new(POLL_INTERVAL, TimerMode::Repeating),
+        })
+        .add_systems(Update, reload_config);
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn reload_config(
+    mut watcher: ResMut<ConfigWatcher>,
+    mut config: ResMut<SimConfig>,
+    time: Res<Time<Real>>,
+) {
+    if !watcher.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = modified_time(&watcher.path);
+    if modified == watcher.last_modified {
+        return;
+    }
+    watcher.last_modified = modified;
+
+    let reloaded = match SimConfig::load(Some(&watcher.path), &watcher.overrides) {
+        Ok(reloaded) => reloaded,
+        Err(error) => {
+            warn!("Keeping previous config: {error}");
+            return;
+        }
+    };
+
+    let applied = apply_tunables(&config, &reloaded);
+    for (key, old, new) in changed_values(&config, &applied) {
+        info!("Config {key}: {old} -> {new}");
+    }
+    for (key, _, new) in changed_values(&applied, &reloaded) {
+        if key != "simulation.seed" {
+            warn!("Config {key} changed to {new}, restart to apply it");
+        }
+    }
+
+    if *config != applied {
+        *config = applied;
+    }
+}
+
+// Returns `current` with every value that can change at runtime taken from `reloaded`
+pub fn apply_tunables(current: &SimConfig, reloaded: &SimConfig) -> SimConfig {
+    let mut applied = current.clone();
+    applied.pheromones = PheromoneConfig {
+        // The grids are allocated once
+        cell_size: current.pheromones.cell_size,
+        ..reloaded.pheromones.clone()
+    };
+    applied.sensing = reloaded.sensing.clone();
+    applied.alarm = reloaded.alarm.clone();
+    applied.states = reloaded.states.clone();
+    applied.ants.speed = reloaded.ants.speed;
+    applied.ants.min_lifetime = reloaded.ants.min_lifetime;
+    applied.ants.max_lifetime = reloaded.ants.max_lifetime;
+    applied.ants.food_consumption = reloaded.ants.food_consumption;
+    applied.ants.starvation_rate = reloaded.ants.starvation_rate;
+    applied.nest.birth_threshold = reloaded.nest.birth_threshold;
+    applied.nest.birth_cost = reloaded.nest.birth_cost;
+    applied
+}
+
+// Lists `(key, old, new)` for every value that differs between two configs
+pub fn changed_values(old: &SimConfig, new: &SimConfig) -> Vec<(String, String, String)> {
+    let old_values = flatten_config(old);
+    let new_values = flatten_config(new);
+    let keys: BTreeSet<&String> = old_values.keys().chain(new_values.keys()).collect();
+
+    let unset = "unset".to_string();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old_value = old_values.get(key).unwrap_or(&unset);
+            let new_value = new_values.get(key).unwrap_or(&unset);
+            (old_value != new_value).then(|| (key.clone(), old_value.clone(), new_value.clone()))
+        })
+        .collect()
+}
+
+// Maps dotted keys such as `pheromones.food_half_life` to their printed value
+fn flatten_config(config: &SimConfig) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    if let Ok(value) = toml::Value::try_from(config) {
+        flatten("", &value, &mut values);
+    }
+    values
+}
+
+fn flatten(prefix: &str, value: &toml::Value, out: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(&key, value, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+#[test]
+fn only_tunable_values_are_applied() {
+    let current = SimConfig::default();
+    let mut reloaded = SimConfig::default();
+    reloaded.pheromones.food_half_life = 30.0;
+    reloaded.sensing.view_angle = 30.0;
+    reloaded.ants.count = 10;
+
+    let applied = apply_tunables(&current, &reloaded);
+
+    let applied_keys: Vec<_> = changed_values(&current, &applied)
+        .into_iter()
+        .map(|(key, _, _)| key)
+        .collect();
+    assert_eq!(applied_keys, ["pheromones.food_half_life", "sensing.view_angle"]);
+    assert_eq!(applied.ants.count, current.ants.count);
+}
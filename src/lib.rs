@@ -0,0 +1,64 @@
+//! Ant colony simulation built on Bevy.
+//!
+//! [`game::SimulationPlugin`] runs the colony without any rendering and works
+//! under `MinimalPlugins`. With the `viewer` feature (enabled by default),
+//! [`viewer::ViewerPlugin`] draws it and [`game::GamePlugin`] bundles both.
+
+pub mod ant;
+pub mod components;
+pub mod config;
+pub mod food;
+pub mod foraging;
+pub mod game;
+pub mod hazards;
+pub mod headless;
+pub mod hot_reload;
+pub mod map;
+pub mod nest;
+pub mod obstacles;
+pub mod pheromones;
+pub mod resources;
+pub mod systems;
+pub mod utils;
+#[cfg(feature = "viewer")]
+pub mod viewer;
+
+pub mod prelude {
+    pub use crate::ant::{AntPlugin, AntStateChanged};
+    pub use crate::components::ant::Ant;
+    pub use crate::components::ant_state::AntState;
+    pub use crate::components::carrying_food::CarryingFood;
+    pub use crate::components::colony_id::ColonyId;
+    pub use crate::components::direction::Direction;
+    pub use crate::components::food::Food;
+    pub use crate::components::hazard::Hazard;
+    pub use crate::components::nest::Nest;
+    pub use crate::components::position::Position;
+    pub use crate::components::role::Role;
+    pub use crate::components::strategy::Strategy;
+    pub use crate::config::{ConfigError, HazardKind, ObstacleShape, SimConfig, StateRule};
+    pub use crate::food::FoodPlugin;
+    pub use crate::foraging::{
+        AntContext, Decision, ForagingStrategy, PheromoneFollowing, Reading, Sensed,
+    };
+    #[cfg(feature = "viewer")]
+    pub use crate::game::GamePlugin;
+    pub use crate::game::SimulationPlugin;
+    pub use crate::hazards::HazardPlugin;
+    pub use crate::headless::{headless_app, headless_app_with_map, run_headless};
+    pub use crate::hot_reload::HotReloadPlugin;
+    pub use crate::map::{ArenaMap, MapError};
+    pub use crate::nest::NestPlugin;
+    pub use crate::obstacles::ObstaclePlugin;
+    pub use crate::pheromones::{
+        AddPheromone, DepositPheromone, PheromoneColor, PheromoneGrid, PheromoneGridTrait,
+        PheromoneGrids, PheromonePlugin, PheromoneSpec, Trail,
+    };
+    pub use crate::resources::colony_stats::{ColonyStat, ColonyStats};
+    pub use crate::resources::foraging_strategies::ForagingStrategies;
+    pub use crate::resources::obstacle_map::ObstacleMap;
+    pub use crate::resources::sim_rng::{RngStream, SimRng};
+    pub use crate::resources::world_bounds::WorldBounds;
+    #[cfg(feature = "viewer")]
+    pub use crate::viewer::ViewerPlugin;
+}
@@ -1,16 +1,66 @@
+use ants::prelude::*;
+#[cfg(feature = "viewer")]
 use bevy::prelude::*;
+#[cfg(feature = "viewer")]
 use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
 
-pub mod ant;
-pub mod food;
-pub mod game;
-pub mod components;
-pub mod pheromones;
-pub mod systems;
-pub mod utils;
+mod cli;
+
+const DEFAULT_HEADLESS_TICKS: u64 = 1000;
+const USAGE: &str = "usage: ants [--config FILE] [--set KEY=VALUE]... [--seed N] [--tick-rate HZ] [--sensing MODEL] [--headless [--ticks N]]";
 
 fn main() {
-    App::new()
+    let args = match cli::Args::parse() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("error: {error}");
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let mut config = match SimConfig::load(args.config.as_deref(), &args.overrides) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("error: {error}");
+            std::process::exit(2);
+        }
+    };
+    // Report a broken map here rather than as a panic while the app is built
+    let map = match ArenaMap::from_config(&config).transpose() {
+        Ok(map) => map,
+        Err(error) => {
+            eprintln!("error: {error}");
+            std::process::exit(2);
+        }
+    };
+    let seed = *config.simulation.seed.get_or_insert_with(rand::random);
+    println!("Seed: {seed}");
+
+    if args.headless {
+        run_headless(config, map, args.ticks.unwrap_or(DEFAULT_HEADLESS_TICKS));
+        return;
+    }
+
+    run_viewer(config, map, args);
+}
+
+// Builds without the `viewer` feature can only run headless
+#[cfg(not(feature = "viewer"))]
+fn run_viewer(_: SimConfig, _: Option<ArenaMap>, _: cli::Args) {
+    eprintln!("error: this build has no viewer; pass --headless");
+    eprintln!("{USAGE}");
+    std::process::exit(2);
+}
+
+#[cfg(feature = "viewer")]
+fn run_viewer(config: SimConfig, map: Option<ArenaMap>, args: cli::Args) {
+    let resolution = Vec2::new(config.window.width, config.window.height);
+    let mut app = App::new();
+    if let Some(map) = map {
+        app.insert_resource(map);
+    }
+    app.insert_resource(config)
         .add_plugins(FrameTimeDiagnosticsPlugin)
         .add_plugins(
             (
@@ -20,7 +70,7 @@ fn main() {
                     primary_window: Some(Window {
                         title: String::from("Ants"),
                         position: WindowPosition::At(IVec2::ZERO),
-                        resolution: Vec2::new(1728., 1050.).into(),
+                        resolution: resolution.into(),
                         resizable: false,
                         ..Default::default()
                     }),
@@ -28,19 +78,28 @@ fn main() {
                 })
                 .set(ImagePlugin::default_nearest()),
 
-                game::GamePlugin,
+                GamePlugin,
             ),
             
         )
-        .add_systems(Update, print_fps)
-        .run();
+        .add_systems(Update, print_fps);
+
+    // Tune parameters live by editing the config file
+    if let Some(path) = SimConfig::resolve_path(args.config.as_deref()) {
+        app.add_plugins(HotReloadPlugin {
+            path,
+            overrides: args.overrides,
+        });
+    }
+
+    app.run();
 }
 
+#[cfg(feature = "viewer")]
 fn print_fps(diagnostics: Res<DiagnosticsStore>) {
-    if let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS) {
-        if let Some(average) = fps.smoothed() {
-            println!("FPS: {:.1}", average);
-        }
+    if let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS)
+        && let Some(average) = fps.smoothed()
+    {
+        println!("FPS: {:.1}", average);
     }
 }
-
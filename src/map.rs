@@ -0,0 +1,429 @@
+use crate::config::{FoodPlacement, SimConfig};
+use crate::resources::world_bounds::WorldBounds;
+use bevy::prelude::*;
+use image::RgbaImage;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// An arena described by an image, loaded from `world.map`. Every pixel is one
+// world unit, with the top row of the image at the top of the world.
+//
+// Colors:
+// - black (or any very dark color): wall
+// - blue: nest, one per connected patch, assigned to the colonies in reading
+//   order
+// - green: food, one source per connected patch, holding `food.amount` for
+//   pure green and proportionally less for darker greens
+// - anything else, including transparent pixels: open ground
+#[derive(Resource, Debug, Clone)]
+pub struct ArenaMap {
+    pub width: usize,
+    pub height: usize,
+    // Row-major, one entry per pixel, bottom row first
+    pub walls: Vec<bool>,
+    pub nests: Vec<Vec2>,
+    // Position and amount of every food patch
+    pub food: Vec<(Vec2, u32)>,
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Image {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    Nests {
+        path: PathBuf,
+        nests: usize,
+        colonies: usize,
+    },
+    NestOutside {
+        path: PathBuf,
+        colony: usize,
+        nest: [f32; 2],
+    },
+    NestInWall {
+        path: PathBuf,
+        colony: usize,
+        nest: [f32; 2],
+    },
+    FoodOutside {
+        path: PathBuf,
+        position: [f32; 2],
+    },
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Image { path, source } => {
+                write!(f, "cannot load map {}: {source}", path.display())
+            }
+            MapError::Nests {
+                path,
+                nests,
+                colonies,
+            } => write!(
+                f,
+                "map {} has {nests} nests but {colonies} colonies are configured",
+                path.display()
+            ),
+            MapError::NestOutside {
+                path,
+                colony,
+                nest: [x, y],
+            } => write!(
+                f,
+                "colonies[{colony}].nest [{x}, {y}] lies outside map {}",
+                path.display()
+            ),
+            MapError::NestInWall {
+                path,
+                colony,
+                nest: [x, y],
+            } => write!(
+                f,
+                "colonies[{colony}].nest [{x}, {y}] lies in a wall of map {}",
+                path.display()
+            ),
+            MapError::FoodOutside {
+                path,
+                position: [x, y],
+            } => write!(
+                f,
+                "food position [{x}, {y}] lies outside map {}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pixel {
+    Ground,
+    Wall,
+    Nest,
+    // Green intensity between 0 and 1
+    Food(f32),
+}
+
+impl Pixel {
+    fn classify([r, g, b, a]: [u8; 4]) -> Pixel {
+        let (r, g, b) = (r as i32, g as i32, b as i32);
+        if a < 128 {
+            Pixel::Ground
+        } else if r.max(g).max(b) < 64 {
+            Pixel::Wall
+        } else if b >= 128 && b > r + 64 && b > g + 64 {
+            Pixel::Nest
+        } else if g > r + 64 && g > b + 64 {
+            Pixel::Food(g as f32 / 255.)
+        } else {
+            Pixel::Ground
+        }
+    }
+}
+
+impl ArenaMap {
+    // The map named in `world.map`, if any
+    pub fn from_config(config: &SimConfig) -> Option<Result<ArenaMap, MapError>> {
+        let path = config.world.map.as_deref()?;
+        Some(ArenaMap::load(path, config))
+    }
+
+    pub fn load(path: &Path, config: &SimConfig) -> Result<ArenaMap, MapError> {
+        let image = image::open(path)
+            .map_err(|source| MapError::Image {
+                path: path.to_path_buf(),
+                source,
+            })?
+            .into_rgba8();
+
+        let map = ArenaMap::from_image(&image, config.food.amount);
+        if !map.nests.is_empty() && map.nests.len() != config.colonies.len() {
+            return Err(MapError::Nests {
+                path: path.to_path_buf(),
+                nests: map.nests.len(),
+                colonies: config.colonies.len(),
+            });
+        }
+        // Configured nests are only used when the map places none
+        if map.nests.is_empty() {
+            let bounds = map.bounds();
+            for (colony, nest) in config.colonies.iter().enumerate() {
+                if let Some(nest) = nest.nest
+                    && !bounds.contains(Vec2::from(nest))
+                {
+                    return Err(MapError::NestOutside {
+                        path: path.to_path_buf(),
+                        colony,
+                        nest,
+                    });
+                }
+                if let Some(nest) = nest.nest
+                    && map.is_wall(Vec2::from(nest))
+                {
+                    return Err(MapError::NestInWall {
+                        path: path.to_path_buf(),
+                        colony,
+                        nest,
+                    });
+                }
+            }
+        }
+        // Likewise for fixed food positions when the map has no food
+        if let FoodPlacement::Fixed { positions } = &config.food.placement
+            && map.food.is_empty()
+            && let Some(position) = positions
+                .iter()
+                .find(|position| !map.bounds().contains(Vec2::from(**position)))
+        {
+            return Err(MapError::FoodOutside {
+                path: path.to_path_buf(),
+                position: *position,
+            });
+        }
+        Ok(map)
+    }
+
+    pub fn from_image(image: &RgbaImage, food_amount: u32) -> ArenaMap {
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+
+        // Flip rows so that the cell index matches world coordinates
+        let pixels: Vec<Pixel> = (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| image.get_pixel(x as u32, (height - 1 - y) as u32).0)
+            })
+            .map(Pixel::classify)
+            .collect();
+
+        let walls = pixels.iter().map(|pixel| *pixel == Pixel::Wall).collect();
+
+        // Patches in reading order: top row first, left to right
+        let mut nests = Vec::new();
+        let mut food = Vec::new();
+        let mut visited = vec![false; pixels.len()];
+        for y in (0..height).rev() {
+            for x in 0..width {
+                let index = y * width + x;
+                if visited[index] {
+                    continue;
+                }
+                match pixels[index] {
+                    Pixel::Nest => {
+                        let patch =
+                            flood_fill(&pixels, width, height, index, &mut visited, |pixel| {
+                                pixel == Pixel::Nest
+                            });
+                        nests.push(centroid(&patch, width));
+                    }
+                    Pixel::Food(_) => {
+                        let patch =
+                            flood_fill(&pixels, width, height, index, &mut visited, |pixel| {
+                                matches!(pixel, Pixel::Food(_))
+                            });
+                        let intensity: f32 = patch
+                            .iter()
+                            .map(|index| match pixels[*index] {
+                                Pixel::Food(intensity) => intensity,
+                                _ => 0.,
+                            })
+                            .sum::<f32>()
+                            / patch.len() as f32;
+                        let amount = ((food_amount as f32 * intensity).round() as u32).max(1);
+                        food.push((centroid(&patch, width), amount));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        ArenaMap {
+            width,
+            height,
+            walls,
+            nests,
+            food,
+        }
+    }
+
+    pub fn bounds(&self) -> WorldBounds {
+        WorldBounds::new(self.width as f32, self.height as f32)
+    }
+
+    // Whether the pixel under a position on the map is wall
+    pub fn is_wall(&self, position: Vec2) -> bool {
+        let (x, y) = (position.x as usize, position.y as usize);
+        x < self.width && y < self.height && self.walls[y * self.width + x]
+    }
+}
+
+// Nest position of the colony with the given index, taken from the map when
+// it places nests
+pub fn nest_position(config: &SimConfig, map: Option<&ArenaMap>, colony: usize) -> Vec2 {
+    match map {
+        Some(map) => match map.nests.get(colony) {
+            Some(position) => *position,
+            None => config.nest_position_within(&map.bounds(), colony),
+        },
+        None => config.nest_position(colony),
+    }
+}
+
+// Indices of the 4-connected patch around `start` whose pixels match `belongs`
+fn flood_fill(
+    pixels: &[Pixel],
+    width: usize,
+    height: usize,
+    start: usize,
+    visited: &mut [bool],
+    belongs: impl Fn(Pixel) -> bool,
+) -> Vec<usize> {
+    let mut patch = Vec::new();
+    let mut stack = vec![start];
+    visited[start] = true;
+
+    while let Some(index) = stack.pop() {
+        patch.push(index);
+        let (x, y) = (index % width, index / width);
+        let neighbours = [
+            (x > 0).then(|| index - 1),
+            (x + 1 < width).then(|| index + 1),
+            (y > 0).then(|| index - width),
+            (y + 1 < height).then(|| index + width),
+        ];
+        for neighbour in neighbours.into_iter().flatten() {
+            if !visited[neighbour] && belongs(pixels[neighbour]) {
+                visited[neighbour] = true;
+                stack.push(neighbour);
+            }
+        }
+    }
+    patch
+}
+
+// World position of the middle of a patch of cells
+fn centroid(patch: &[usize], width: usize) -> Vec2 {
+    let sum: Vec2 = patch
+        .iter()
+        .map(|index| Vec2::new((index % width) as f32, (index / width) as f32))
+        .sum();
+    sum / patch.len() as f32 + Vec2::splat(0.5)
+}
+
+#[test]
+fn image_colors_become_walls_nests_and_food() {
+    let mut image = RgbaImage::from_pixel(8, 4, image::Rgba([255, 255, 255, 255]));
+    // A wall along the left edge
+    for y in 0..4 {
+        image.put_pixel(0, y, image::Rgba([0, 0, 0, 255]));
+    }
+    // A 2x2 nest in the top right corner
+    for (x, y) in [(6, 0), (7, 0), (6, 1), (7, 1)] {
+        image.put_pixel(x, y, image::Rgba([0, 0, 255, 255]));
+    }
+    // A half-intensity food pixel in the bottom row
+    image.put_pixel(3, 3, image::Rgba([0, 128, 0, 255]));
+
+    let map = ArenaMap::from_image(&image, 100);
+
+    assert_eq!((map.width, map.height), (8, 4));
+    assert_eq!(map.walls.iter().filter(|wall| **wall).count(), 4);
+    // The top of the image is the top of the world
+    assert_eq!(map.nests, [Vec2::new(7., 3.)]);
+    assert_eq!(map.food, [(Vec2::new(3.5, 0.5), 50)]);
+}
+
+#[test]
+fn map_sizes_the_world_and_places_nest_and_food() {
+    use crate::components::food::Food;
+    use crate::components::nest::Nest;
+    use crate::components::position::Position;
+
+    let mut image = RgbaImage::from_pixel(120, 80, image::Rgba([255, 255, 255, 255]));
+    image.put_pixel(20, 40, image::Rgba([0, 0, 255, 255]));
+    image.put_pixel(100, 40, image::Rgba([0, 255, 0, 255]));
+    let path = std::env::temp_dir().join(format!("ants-map-{}.png", std::process::id()));
+    image.save(&path).unwrap();
+
+    let mut config = SimConfig::default();
+    config.world.map = Some(path.clone());
+    config.ants.count = 10;
+    let mut app = crate::headless::headless_app(config);
+    app.update();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(*app.world().resource::<WorldBounds>(), WorldBounds::new(120., 80.));
+    let mut nests = app.world_mut().query_filtered::<&Position, With<Nest>>();
+    assert_eq!(nests.single(app.world()).position, Vec2::new(20.5, 39.5));
+    let mut food = app.world_mut().query::<(&Position, &Food)>();
+    let (position, food) = food.single(app.world());
+    assert_eq!((position.position, food.remaining), (Vec2::new(100.5, 39.5), 500));
+}
+
+#[test]
+fn configured_nests_must_lie_on_the_map() {
+    let image = RgbaImage::from_pixel(120, 80, image::Rgba([255, 255, 255, 255]));
+    let path = std::env::temp_dir().join(format!("ants-nest-map-{}.png", std::process::id()));
+    image.save(&path).unwrap();
+
+    // Inside the configured world, which the map replaces
+    let mut config = SimConfig::default();
+    config.world.map = Some(path.clone());
+    config.colonies[0].nest = Some([500., 500.]);
+    assert!(config.validate().is_ok());
+    let loaded = ArenaMap::load(&path, &config);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        matches!(loaded, Err(MapError::NestOutside { colony: 0, .. })),
+        "{loaded:?}"
+    );
+}
+
+#[test]
+fn configured_nests_must_not_lie_in_a_wall() {
+    let mut image = RgbaImage::from_pixel(120, 80, image::Rgba([255, 255, 255, 255]));
+    // A wall across the middle of the map
+    for y in 0..80 {
+        image.put_pixel(60, y, image::Rgba([0, 0, 0, 255]));
+    }
+    let path = std::env::temp_dir().join(format!("ants-wall-map-{}.png", std::process::id()));
+    image.save(&path).unwrap();
+
+    let mut config = SimConfig::default();
+    config.world.map = Some(path.clone());
+    config.colonies[0].nest = Some([60.5, 10.]);
+    let loaded = ArenaMap::load(&path, &config);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        matches!(loaded, Err(MapError::NestInWall { colony: 0, .. })),
+        "{loaded:?}"
+    );
+}
+
+#[test]
+fn fixed_food_must_lie_on_the_map() {
+    let image = RgbaImage::from_pixel(120, 80, image::Rgba([255, 255, 255, 255]));
+    let path = std::env::temp_dir().join(format!("ants-food-map-{}.png", std::process::id()));
+    image.save(&path).unwrap();
+
+    // Inside the configured world, which the map replaces
+    let mut config = SimConfig::default();
+    config.world.map = Some(path.clone());
+    config.food.placement = FoodPlacement::Fixed {
+        positions: vec![[100., 50.], [500., 50.]],
+    };
+    assert!(config.validate().is_ok());
+    let loaded = ArenaMap::load(&path, &config);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        matches!(loaded, Err(MapError::FoodOutside { position: [500., 50.], .. })),
+        "{loaded:?}"
+    );
+}
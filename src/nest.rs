@@ -0,0 +1,50 @@
+use crate::components::colony_id::ColonyId;
+use crate::components::food_store::FoodStore;
+use crate::components::nest::Nest;
+use crate::components::position::Position;
+use crate::config::SimConfig;
+use crate::map::{ArenaMap, nest_position};
+use bevy::prelude::*;
+
+pub struct NestPlugin;
+
+impl Plugin for NestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_nest);
+    }
+}
+
+// Spawns one nest for every configured colony
+pub fn spawn_nest(mut commands: Commands, config: Res<SimConfig>, map: Option<Res<ArenaMap>>) {
+    for colony in 0..config.colonies.len() {
+        commands.spawn((
+            Nest {
+                radius: config.nest.radius,
+            },
+            ColonyId(colony),
+            Position {
+                position: nest_position(&config, map.as_deref(), colony),
+            },
+            FoodStore {
+                amount: config.nest.initial_food,
+            },
+        ));
+    }
+}
+
+// The nest of `colony` closest to `position`, if the colony has one
+pub fn nearest_nest<'a>(
+    nests: impl IntoIterator<Item = (Entity, &'a Position, &'a Nest, &'a ColonyId)>,
+    colony: ColonyId,
+    position: Vec2,
+) -> Option<(Entity, &'a Position, &'a Nest)> {
+    nests
+        .into_iter()
+        .filter(|(_, _, _, nest_colony)| **nest_colony == colony)
+        .map(|(entity, nest_position, nest, _)| (entity, nest_position, nest))
+        .min_by(|(_, a, _), (_, b, _)| {
+            a.position
+                .distance_squared(position)
+                .total_cmp(&b.position.distance_squared(position))
+        })
+}
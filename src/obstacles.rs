@@ -0,0 +1,36 @@
+use crate::components::obstacle::Obstacle;
+use crate::config::SimConfig;
+use crate::map::ArenaMap;
+use crate::resources::obstacle_map::ObstacleMap;
+use crate::resources::world_bounds::WorldBounds;
+use bevy::prelude::*;
+
+pub struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ObstacleMap>()
+            .add_systems(Startup, setup_obstacles);
+    }
+}
+
+// Rasterises the configured obstacles and the walls of the arena map, and
+// spawns one entity for each configured obstacle
+pub fn setup_obstacles(
+    mut commands: Commands,
+    mut obstacle_map: ResMut<ObstacleMap>,
+    bounds: Res<WorldBounds>,
+    map: Option<Res<ArenaMap>>,
+    config: Res<SimConfig>,
+) {
+    *obstacle_map = ObstacleMap::new(&bounds, &config.obstacles);
+    if let Some(map) = map {
+        obstacle_map.block_cells(&map.walls);
+    }
+
+    for shape in &config.obstacles {
+        commands.spawn(Obstacle {
+            shape: shape.clone(),
+        });
+    }
+}
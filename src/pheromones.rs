@@ -1,17 +1,23 @@
 use crate::components::ant::Ant;
 use crate::components::carrying_food::CarryingFood;
+use crate::components::colony_id::ColonyId;
+use crate::components::depositing::Depositing;
 use crate::components::position::Position;
-use crate::systems::setup_pheromone_texture::setup_pheromone_texture;
+use crate::components::travelled::Travelled;
+use crate::config::{AlarmConfig, BoundaryMode, PheromoneConfig, SimConfig};
+use crate::resources::obstacle_map::ObstacleMap;
+use crate::resources::world_bounds::WorldBounds;
+use bevy::ecs::component::{ComponentId, Tick};
+use bevy::ecs::system::{
+    FilteredResourcesParamBuilder, ReadOnlySystemParam, SystemMeta, SystemParam,
+    SystemParamBuilder,
+};
+use bevy::ecs::world::FilteredResources;
+use bevy::ecs::world::unsafe_world_cell::UnsafeWorldCell;
 use bevy::prelude::*;
-use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
-use bevy::window::PrimaryWindow;
 use rayon::prelude::*;
 use std::marker::PhantomData;
-
-// Constants
-const PHEROMONE_DECAY_RATE: f32 = 0.99999;
-const PHEROMONE_INCREMENT: f32 = 0.02;
-const FOOD_PHEROMONE_INCREMENT: f32 = 0.05; // 5 times stronger for Food pheromone
+use std::time::Duration;
 
 // Pheromone types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -25,54 +31,412 @@ pub enum PheromoneType {
 pub struct Nest;
 #[derive(Default)]
 pub struct Food;
+#[derive(Default)]
+pub struct Alarm;
 
+// Adds the built-in nest, food and alarm pheromones. Further kinds are added
+// with `AddPheromone::add_pheromone`.
 pub struct PheromonePlugin;
 
 impl Plugin for PheromonePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<PheromoneGrid<Nest>>()
-            .init_resource::<PheromoneGrid<Food>>()
-            .add_systems(
-                Startup,
-                (
-                    setup_pheromone_grid::<Nest>,
-                    setup_pheromone_grid::<Food>,
-                    setup_pheromone_texture::<Nest>,
-                    setup_pheromone_texture::<Food>,
-                ),
-            )
+        let config = app
+            .world()
+            .get_resource::<SimConfig>()
+            .cloned()
+            .unwrap_or_default();
+        app.add_pheromone::<Nest>(PheromoneSpec::nest(&config.pheromones))
+            .add_pheromone::<Food>(PheromoneSpec::food(&config.pheromones))
+            .add_pheromone::<Alarm>(PheromoneSpec::alarm(&config.alarm))
             .add_systems(
-                Update,
-                (
-                    update_pheromone_grid::<Nest>,
-                    update_pheromone_grid::<Food>,
-                    update_pheromone_texture::<Nest>,
-                    update_pheromone_texture::<Food>,
-                ),
+                FixedUpdate,
+                apply_pheromone_config
+                    .run_if(resource_changed::<SimConfig>)
+                    .before(PheromoneSystems),
             );
     }
 }
 
+// Grid updates of every pheromone kind: deposits, evaporation and diffusion
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PheromoneSystems;
+
+// Which ants leave a pheromone wherever they walk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trail {
+    // Ants looking for food
+    Searching,
+    // Ants carrying food home
+    Carrying,
+    // No ant; the pheromone is only laid through `DepositPheromone` events
+    Events,
+}
+
+impl Trail {
+    fn laid_by(self, carrying_food: bool) -> bool {
+        match self {
+            Trail::Searching => !carrying_food,
+            Trail::Carrying => carrying_food,
+            Trail::Events => false,
+        }
+    }
+}
+
+// Everything the simulation needs to know about a pheromone kind
+#[derive(Debug, Clone)]
+pub struct PheromoneSpec {
+    // Color of the trails in the viewer
+    pub color: PheromoneColor,
+    // Pheromone deposited per simulated second by each ant on the trail
+    pub increment: f32,
+    // Simulated seconds for levels to halve
+    pub half_life: f32,
+    pub trail: Trail,
+}
+
+impl PheromoneSpec {
+    // Laid by ants looking for food, leading back to the nest
+    pub fn nest(config: &PheromoneConfig) -> Self {
+        Self {
+            color: PheromoneColor {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 255,
+            },
+            increment: config.nest_deposit,
+            half_life: config.nest_half_life,
+            trail: Trail::Searching,
+        }
+    }
+
+    // Laid by ants carrying food, leading to the food
+    pub fn food(config: &PheromoneConfig) -> Self {
+        Self {
+            color: PheromoneColor {
+                r: 20,
+                g: 100,
+                b: 20,
+                a: 255,
+            }, // Green for food pheromones
+            increment: config.food_deposit,
+            half_life: config.food_half_life,
+            trail: Trail::Carrying,
+        }
+    }
+
+    // Laid by ants inside a hazard, see `crate::hazards`
+    pub fn alarm(config: &AlarmConfig) -> Self {
+        Self {
+            color: PheromoneColor {
+                r: 200,
+                g: 30,
+                b: 30,
+                a: 255,
+            },
+            increment: config.deposit,
+            half_life: config.half_life,
+            trail: Trail::Events,
+        }
+    }
+}
+
+// Lays `amount` of pheromone `T` in a colony's layer at a world position, for
+// deposits that don't follow an ant's trail
+#[derive(Event)]
+pub struct DepositPheromone<T: Send + Sync + 'static> {
+    pub position: Vec2,
+    pub colony: ColonyId,
+    pub amount: f32,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Send + Sync + 'static> DepositPheromone<T> {
+    pub fn new(position: Vec2, colony: ColonyId, amount: f32) -> Self {
+        Self {
+            position,
+            colony,
+            amount,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// Every registered pheromone kind in registration order, for code that treats
+// all kinds alike such as the viewer
+#[derive(Resource, Default)]
+pub struct PheromoneRegistry {
+    kinds: Vec<RegisteredPheromone>,
+}
+
+impl PheromoneRegistry {
+    pub fn kinds(&self) -> &[RegisteredPheromone] {
+        &self.kinds
+    }
+}
+
+pub struct RegisteredPheromone {
+    pub color: PheromoneColor,
+    grid: ComponentId,
+    layers: for<'w> fn(&'w World) -> &'w [PheromoneLayer],
+    changed: fn(&World) -> bool,
+}
+
+impl RegisteredPheromone {
+    // The kind's layers, one per colony
+    pub fn layers<'w>(&self, world: &'w World) -> &'w [PheromoneLayer] {
+        (self.layers)(world)
+    }
+
+    // Whether the kind's grid changed since the calling system last ran
+    pub fn is_changed(&self, world: &World) -> bool {
+        (self.changed)(world)
+    }
+}
+
+// Reads the grid of every registered pheromone kind, so systems can handle
+// kinds added after them. Kinds are looked up when the system first runs.
+pub struct PheromoneGrids<'w, 's>(FilteredResources<'w, 's>);
+
+impl<'w> PheromoneGrids<'w, '_> {
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<&'w PheromoneGrid<T>> {
+        self.0.get::<PheromoneGrid<T>>().map(Ref::into_inner)
+    }
+}
+
+// SAFETY: `init_state` registers a read of every grid through the builder,
+// which panics on conflicts, and `get_param` only passes that access on
+unsafe impl SystemParam for PheromoneGrids<'_, '_> {
+    type State = <FilteredResources<'static, 'static> as SystemParam>::State;
+    type Item<'w, 's> = PheromoneGrids<'w, 's>;
+
+    fn init_state(world: &mut World, system_meta: &mut SystemMeta) -> Self::State {
+        let grids: Vec<_> = world
+            .get_resource::<PheromoneRegistry>()
+            .map(|registry| registry.kinds.iter().map(|kind| kind.grid).collect())
+            .unwrap_or_default();
+        let builder = FilteredResourcesParamBuilder::new(|builder| {
+            for grid in grids {
+                builder.add_read_by_id(grid);
+            }
+        });
+        SystemParamBuilder::<FilteredResources>::build(builder, world, system_meta)
+    }
+
+    unsafe fn get_param<'w, 's>(
+        state: &'s mut Self::State,
+        system_meta: &SystemMeta,
+        world: UnsafeWorldCell<'w>,
+        change_tick: Tick,
+    ) -> Self::Item<'w, 's> {
+        // SAFETY: forwarded from the caller
+        PheromoneGrids(unsafe {
+            FilteredResources::get_param(state, system_meta, world, change_tick)
+        })
+    }
+}
+
+// SAFETY: only ever reads
+unsafe impl ReadOnlySystemParam for PheromoneGrids<'_, '_> {}
+
+fn grid_layers<T: Send + Sync + 'static>(world: &World) -> &[PheromoneLayer] {
+    &world.resource::<PheromoneGrid<T>>().layers
+}
+
+fn grid_changed<T: Send + Sync + 'static>(world: &World) -> bool {
+    world.is_resource_changed::<PheromoneGrid<T>>()
+}
+
+pub trait AddPheromone {
+    // Adds a grid for pheromone kind `T`, which ants deposit on according to
+    // `spec.trail`, which evaporates and diffuses like every other kind and
+    // which the viewer draws over the kinds registered before it. Each kind
+    // may only be registered once.
+    fn add_pheromone<T: Send + Sync + 'static>(&mut self, spec: PheromoneSpec) -> &mut Self;
+}
+
+impl AddPheromone for App {
+    fn add_pheromone<T: Send + Sync + 'static>(&mut self, spec: PheromoneSpec) -> &mut Self {
+        assert!(
+            !self.world().contains_resource::<PheromoneGrid<T>>(),
+            "pheromone kind {} is already registered",
+            std::any::type_name::<T>()
+        );
+        self.init_resource::<PheromoneRegistry>();
+        let grid = self.world_mut().register_resource::<PheromoneGrid<T>>();
+        self.world_mut()
+            .resource_mut::<PheromoneRegistry>()
+            .kinds
+            .push(RegisteredPheromone {
+                color: spec.color,
+                grid,
+                layers: grid_layers::<T>,
+                changed: grid_changed::<T>,
+            });
+        self.insert_resource(PheromoneGrid::<T>::new(spec))
+            .add_event::<DepositPheromone<T>>()
+            .add_systems(Startup, setup_pheromone_grid::<T>)
+            .add_systems(
+                FixedUpdate,
+                (update_pheromone_grid::<T>, diffuse_pheromone_grid::<T>)
+                    .chain()
+                    .in_set(PheromoneSystems),
+            )
+    }
+}
+
+// Keeps the built-in kinds in step with `[pheromones]` and `[alarm]`, which
+// may be hot-reloaded
+fn apply_pheromone_config(
+    config: Res<SimConfig>,
+    mut nest_grid: ResMut<PheromoneGrid<Nest>>,
+    mut food_grid: ResMut<PheromoneGrid<Food>>,
+    mut alarm_grid: ResMut<PheromoneGrid<Alarm>>,
+) {
+    nest_grid.spec = PheromoneSpec::nest(&config.pheromones);
+    food_grid.spec = PheromoneSpec::food(&config.pheromones);
+    alarm_grid.spec = PheromoneSpec::alarm(&config.alarm);
+}
+
 pub trait PheromoneGridTrait {
-    fn get_grid(&self) -> &Vec<Vec<f32>>;
+    fn layer(&self, colony: ColonyId) -> &PheromoneLayer;
+    // Level of `colony`'s layer at a world position, see `PheromoneLayer::sample`
+    fn sample(&self, colony: ColonyId, position: Vec2) -> f32;
     fn get_width(&self) -> usize;
     fn get_height(&self) -> usize;
 }
 
-#[derive(Resource, Default)]
+// Cells per evaporation chunk. Chunks holding nothing but zeros are skipped,
+// so evaporation costs scale with the area trails cover.
+const CHUNK_CELLS: usize = 4096;
+
+// Pheromone levels of one colony, stored row by row starting at the bottom of
+// the world. Positions are in cells, not world units.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PheromoneLayer {
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+    // Whether each chunk of `CHUNK_CELLS` cells may hold pheromone
+    active: Vec<bool>,
+}
+
+impl PheromoneLayer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            values: vec![0.0; width * height],
+            active: vec![false; (width * height).div_ceil(CHUNK_CELLS)],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> f32 {
+        self.values[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: f32) {
+        let index = y * self.width + x;
+        self.values[index] = value;
+        self.active[index / CHUNK_CELLS] = true;
+    }
+
+    // Adds `amount` to a cell without letting it exceed 1
+    pub fn add_clamped(&mut self, x: usize, y: usize, amount: f32) {
+        let index = y * self.width + x;
+        let value = &mut self.values[index];
+        *value = (*value + amount).min(1.0);
+        self.active[index / CHUNK_CELLS] = true;
+    }
+
+    // Multiplies every cell by `factor`, dropping levels at or below `floor`
+    // to zero
+    pub fn evaporate(&mut self, factor: f32, floor: f32) {
+        self.values
+            .par_chunks_mut(CHUNK_CELLS)
+            .zip(self.active.par_iter_mut())
+            .filter(|(_, active)| **active)
+            .for_each(|(cells, active)| {
+                let mut remaining = false;
+                for value in cells {
+                    *value *= factor;
+                    if *value <= floor {
+                        *value = 0.0;
+                    } else {
+                        remaining = true;
+                    }
+                }
+                *active = remaining;
+            });
+    }
+
+    // Whether every cell is known to be empty
+    pub fn is_clear(&self) -> bool {
+        !self.active.contains(&true)
+    }
+
+    // Level at a position in cells, interpolated between the four nearest cell
+    // centres. Positions past the edge take the value of the edge cells.
+    pub fn sample(&self, position: Vec2) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let max = Vec2::new((self.width - 1) as f32, (self.height - 1) as f32);
+        let cell = (position - Vec2::splat(0.5)).clamp(Vec2::ZERO, max);
+        let (x0, y0) = (cell.x as usize, cell.y as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let t = cell - Vec2::new(x0 as f32, y0 as f32);
+
+        let bottom = self.get(x0, y0) * (1. - t.x) + self.get(x1, y0) * t.x;
+        let top = self.get(x0, y1) * (1. - t.x) + self.get(x1, y1) * t.x;
+        bottom * (1. - t.y) + top * t.y
+    }
+
+    // All cells, row by row
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    // Callers may write anywhere, so every chunk counts as active until the
+    // next evaporation
+    pub fn values_mut(&mut self) -> &mut [f32] {
+        self.active.fill(true);
+        &mut self.values
+    }
+}
+
+#[derive(Resource)]
 pub struct PheromoneGrid<T: Send + Sync + 'static> {
-    pub grid: Vec<Vec<f32>>,
+    pub spec: PheromoneSpec,
+    // One layer per colony, indexed by `ColonyId`, so trails only guide the
+    // colony that laid them
+    pub layers: Vec<PheromoneLayer>,
+    // Size of the grid in cells
     pub width: usize,
     pub height: usize,
-    pub texture_handle: Option<Handle<Image>>,
-    pub texture_entity: Option<Entity>,
+    // World units covered by the side of one cell
+    pub cell_size: f32,
     pub blur_timer: Timer,
+    // Cells whose centre lies in an obstacle, refreshed whenever the obstacles
+    // change; empty while there are none
+    blocked: Vec<bool>,
     _marker: PhantomData<T>,
 }
 
 impl<T: Send + Sync + 'static> PheromoneGridTrait for PheromoneGrid<T> {
-    fn get_grid(&self) -> &Vec<Vec<f32>> {
-        &self.grid
+    fn layer(&self, colony: ColonyId) -> &PheromoneLayer {
+        &self.layers[colony.0]
+    }
+    fn sample(&self, colony: ColonyId, position: Vec2) -> f32 {
+        self.layers[colony.0].sample(self.world_to_grid(position))
     }
     fn get_width(&self) -> usize {
         self.width
@@ -82,176 +446,446 @@ impl<T: Send + Sync + 'static> PheromoneGridTrait for PheromoneGrid<T> {
     }
 }
 
+impl<T: Send + Sync + 'static> PheromoneGrid<T> {
+    // An empty grid, sized by `setup_pheromone_grid`
+    pub fn new(spec: PheromoneSpec) -> Self {
+        Self {
+            spec,
+            layers: Vec::new(),
+            width: 0,
+            height: 0,
+            cell_size: 1.0,
+            blur_timer: Timer::default(),
+            blocked: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    // Converts a world position to continuous grid coordinates, in cells
+    pub fn world_to_grid(&self, position: Vec2) -> Vec2 {
+        position / self.cell_size
+    }
+
+    // World position of the centre of a cell
+    pub fn grid_to_world(&self, x: usize, y: usize) -> Vec2 {
+        (Vec2::new(x as f32, y as f32) + Vec2::splat(0.5)) * self.cell_size
+    }
+
+    // The cell containing an in-world position
+    pub fn cell_at(&self, position: Vec2) -> (usize, usize) {
+        let cell = self.world_to_grid(position);
+        (
+            (cell.x as usize).min(self.width.saturating_sub(1)),
+            (cell.y as usize).min(self.height.saturating_sub(1)),
+        )
+    }
+}
+
 // Setup pheromone grid
-fn setup_pheromone_grid<T: Send + Sync + 'static>(
+pub fn setup_pheromone_grid<T: Send + Sync + 'static>(
     pheromone_grid: ResMut<PheromoneGrid<T>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    bounds: Res<WorldBounds>,
+    config: Res<SimConfig>,
 ) {
-    let window = window_query.get_single().unwrap();
-    let width = window.width() as usize;
-    let height = window.height() as usize;
+    // Enough cells to cover the world; the last ones may stick out past it
+    let cell_size = config.pheromones.cell_size;
+    let width = (bounds.width / cell_size).ceil() as usize;
+    let height = (bounds.height / cell_size).ceil() as usize;
 
-    // Initialize the grid with zeros
-    let grid = vec![vec![0.0; height]; width];
+    // Initialize a layer of zeros for every colony
+    let layers = vec![PheromoneLayer::new(width, height); config.colonies.len()];
 
     let grid_inner = pheromone_grid.into_inner();
-    grid_inner.grid = grid;
+    grid_inner.layers = layers;
     grid_inner.width = width;
     grid_inner.height = height;
+    grid_inner.cell_size = cell_size;
+    grid_inner.blur_timer =
+        Timer::from_seconds(config.pheromones.diffusion_interval, TimerMode::Repeating);
 }
 
-// Trait to get query filter and color for each pheromone type
-trait PheromoneTypeInfo: Send + Sync {
-    type QueryFilter: bevy::ecs::query::QueryFilter;
-    fn color() -> PheromoneColor;
-}
+type Depositor = (
+    &'static Position,
+    &'static ColonyId,
+    Has<CarryingFood>,
+    Option<&'static Travelled>,
+    Option<&'static Depositing>,
+);
+
+// Lays trails and deposit events on the grid of one pheromone kind, then lets
+// it evaporate
+fn update_pheromone_grid<T: Send + Sync + 'static>(
+    pheromone_grid: ResMut<PheromoneGrid<T>>,
+    ant_query: Query<Depositor, With<Ant>>,
+    mut deposits: EventReader<DepositPheromone<T>>,
+    obstacles: Res<ObstacleMap>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+) {
+    let grid_inner = pheromone_grid.into_inner();
+    let delta = time.delta_secs();
+    let trail = grid_inner.spec.trail;
+    let deposit = grid_inner.spec.increment * delta;
+    // Share left after this tick, so trails halve every half-life whatever
+    // the tick rate
+    let evaporation = 0.5f32.powf(delta / grid_inner.spec.half_life);
+
+    // Increase pheromone level at each depositing ant's position, in its
+    // colony's layer, and wherever a deposit event asks for it
+    let half_distance = config.pheromones.deposit_half_distance;
+    let trail_deposits = ant_query
+        .iter()
+        .filter(|(_, _, carrying_food, _, depositing)| {
+            // The ant's strategy may hold its trail back
+            trail.laid_by(*carrying_food) && depositing.is_none_or(|depositing| depositing.active)
+        })
+        .map(|(position, colony, _, travelled, _)| {
+            // Ants far from where they came from lay weaker trails, so lost
+            // ants don't reinforce loops
+            let distance = travelled.map_or(0.0, |travelled| travelled.distance);
+            let strength = 0.5f32.powf(distance / half_distance);
+            (position.position, *colony, deposit * strength)
+        });
+    let event_deposits = deposits
+        .read()
+        .map(|event| (event.position, event.colony, event.amount));
+    for (position, colony, amount) in trail_deposits.chain(event_deposits) {
+        // Nothing sticks outside the world or to obstacles
+        let Some(position) = bounds.lookup(position) else {
+            continue;
+        };
+        if obstacles.is_blocked(position) {
+            continue;
+        }
 
-// Implement for Nest type
-impl PheromoneTypeInfo for Nest {
-    type QueryFilter = (With<Ant>, Without<CarryingFood>);
+        // Convert world coordinates to grid coordinates
+        let (grid_x, grid_y) = grid_inner.cell_at(position);
+        let Some(layer) = grid_inner.layers.get_mut(colony.0) else {
+            continue;
+        };
 
-    fn color() -> PheromoneColor {
-        PheromoneColor {
-            r: 0,
-            g: 0,
-            b: 0,
-            a: 255,
-        }
+        // Increase pheromone level at this position
+        layer.add_clamped(grid_x, grid_y, amount);
+    }
+
+    let floor = config.pheromones.evaporation_floor;
+    for layer in grid_inner.layers.iter_mut() {
+        layer.evaporate(evaporation, floor);
     }
 }
 
-// Implement for Food type
-impl PheromoneTypeInfo for Food {
-    type QueryFilter = (With<Ant>, With<CarryingFood>);
+// Spreads pheromone to neighbouring cells every `diffusion_interval`, so
+// trails widen into gradients
+fn diffuse_pheromone_grid<T: Send + Sync + 'static>(
+    pheromone_grid: ResMut<PheromoneGrid<T>>,
+    obstacles: Res<ObstacleMap>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    config: Res<SimConfig>,
+) {
+    let grid_inner = pheromone_grid.into_inner();
+    // The interval may have been hot-reloaded
+    let interval = Duration::from_secs_f32(config.pheromones.diffusion_interval);
+    if grid_inner.blur_timer.duration() != interval {
+        grid_inner.blur_timer.set_duration(interval);
+    }
+    let passes = grid_inner.blur_timer.tick(time.delta()).times_finished_this_tick();
+    // Obstacles are rasterised per world unit; look up each cell centre once.
+    // Done even while diffusion is off, as the rate may be hot-reloaded later.
+    if obstacles.is_changed() {
+        let grid = &*grid_inner;
+        let blocked = if obstacles.is_empty() {
+            Vec::new()
+        } else {
+            (0..grid.width * grid.height)
+                .map(|index| grid.grid_to_world(index % grid.width, index / grid.width))
+                .map(|centre| obstacles.is_blocked(centre))
+                .collect()
+        };
+        grid_inner.blocked = blocked;
+    }
 
-    fn color() -> PheromoneColor {
-        PheromoneColor {
-            r: 20,
-            g: 100,
-            b: 20,
-            a: 255,
-        } // Green for food pheromones
+    let rate = config.pheromones.diffusion_rate;
+    if rate == 0.0 {
+        return;
     }
-}
 
-// Trait to get the increment value for each pheromone type
-trait PheromoneIncrement {
-    fn increment() -> f32;
+    let radius = config.pheromones.diffusion_kernel / 2;
+    let wrap = bounds.boundary == BoundaryMode::Wrap;
+    for _ in 0..passes {
+        // Layers without any trail stay empty
+        for layer in grid_inner.layers.iter_mut().filter(|layer| !layer.is_clear()) {
+            diffuse(layer, radius, rate, wrap, &grid_inner.blocked);
+        }
+    }
 }
 
-// Default increment for Nest pheromones
-impl PheromoneIncrement for Nest {
-    fn increment() -> f32 {
-        PHEROMONE_INCREMENT
+// Blends every cell with the average of the square of cells within `radius`.
+// Neighbours past the edge are taken from the opposite side when `wrap` is set
+// and left out of the average otherwise. Whatever spreads onto a `blocked`
+// cell is lost.
+fn diffuse(
+    layer: &mut PheromoneLayer,
+    radius: usize,
+    rate: f32,
+    wrap: bool,
+    blocked: &[bool],
+) {
+    let (width, height) = (layer.width(), layer.height());
+    if width == 0 || height == 0 {
+        return;
     }
+    let radius = radius as isize;
+    let neighbours = |index: usize, size: usize| {
+        (-radius..=radius).filter_map(move |offset| {
+            let neighbour = index as isize + offset;
+            if wrap {
+                Some(neighbour.rem_euclid(size as isize) as usize)
+            } else {
+                (0..size as isize)
+                    .contains(&neighbour)
+                    .then_some(neighbour as usize)
+            }
+        })
+    };
+
+    // The box average is separable: average along each row first, then
+    // along the columns of those averages
+    let row_averages: Vec<f32> = layer
+        .values()
+        .par_chunks(width)
+        .flat_map_iter(|row| {
+            (0..width).map(move |x| {
+                let (sum, count) = neighbours(x, width)
+                    .fold((0.0, 0), |(sum, count), x| (sum + row[x], count + 1));
+                sum / count as f32
+            })
+        })
+        .collect();
+
+    layer
+        .values
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, value) in row.iter_mut().enumerate() {
+                let (sum, count) = neighbours(y, height).fold((0.0, 0), |(sum, count), y| {
+                    (sum + row_averages[y * width + x], count + 1)
+                });
+                *value += rate * (sum / count as f32 - *value);
+            }
+        });
+
+    // Trails spread into neighbouring chunks, but chunks they don't reach
+    // stay skipped by evaporation
+    layer
+        .values
+        .par_chunks_mut(CHUNK_CELLS)
+        .zip(layer.active.par_iter_mut())
+        .enumerate()
+        .for_each(|(chunk, (cells, active))| {
+            if !blocked.is_empty() {
+                let blocked = &blocked[chunk * CHUNK_CELLS..][..cells.len()];
+                for (value, blocked) in cells.iter_mut().zip(blocked) {
+                    if *blocked {
+                        *value = 0.0;
+                    }
+                }
+            }
+            *active = cells.iter().any(|value| *value > 0.0);
+        });
 }
 
-// Stronger increment for Food pheromones
-impl PheromoneIncrement for Food {
-    fn increment() -> f32 {
-        FOOD_PHEROMONE_INCREMENT
-    }
+// Helper struct to define pheromone color channels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PheromoneColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
 }
 
-// Generic function to update pheromone grids
-fn update_pheromone_grid<T: Send + Sync + 'static + PheromoneTypeInfo + PheromoneIncrement>(
-    pheromone_grid: ResMut<PheromoneGrid<T>>,
-    ant_query: Query<&Position, T::QueryFilter>,
-) {
-    let grid_inner = pheromone_grid.into_inner();
+#[test]
+fn diffusion_spreads_a_spike_to_its_neighbours() {
+    let mut layer = PheromoneLayer::new(5, 5);
+    layer.set(2, 2, 1.0);
 
-    // Increase pheromone level at each ant's position
-    for position in ant_query.iter() {
-        // Convert world coordinates to grid coordinates
-        let grid_x = (position.position.x as usize).clamp(0, grid_inner.width.saturating_sub(1));
-        let grid_y = (position.position.y as usize).clamp(0, grid_inner.height.saturating_sub(1));
+    diffuse(&mut layer, 1, 0.5, false, &[]);
 
-        // Increase pheromone level at this position
-        let current_value = grid_inner.grid[grid_x][grid_y];
-        let new_value = (current_value + T::increment()).min(1.0);
-        grid_inner.grid[grid_x][grid_y] = new_value;
-    }
+    assert!((layer.get(2, 2) - (0.5 + 0.5 / 9.0)).abs() < 1e-6);
+    assert!((layer.get(1, 3) - 0.5 / 9.0).abs() < 1e-6);
+    assert_eq!(layer.get(0, 0), 0.0);
+    let total: f32 = layer.values().iter().sum();
+    assert!((total - 1.0).abs() < 1e-6);
+}
 
-    grid_inner.grid.par_iter_mut().for_each(|row| {
-        row.iter_mut()
-            .for_each(|value| *value *= PHEROMONE_DECAY_RATE);
-    });
+#[test]
+fn layer_samples_between_cell_centres() {
+    let mut layer = PheromoneLayer::new(4, 3);
+    layer.set(1, 1, 1.0);
+    layer.add_clamped(2, 1, 0.25);
+    layer.add_clamped(2, 1, 1.0);
+
+    assert_eq!(layer.get(2, 1), 1.0);
+    assert_eq!(layer.values()[4 + 1], 1.0);
+    assert_eq!(layer.sample(Vec2::new(1.5, 1.5)), 1.0);
+    assert_eq!(layer.sample(Vec2::new(1.5, 2.0)), 0.5);
+    assert_eq!(layer.sample(Vec2::new(0.5, 1.5)), 0.0);
+    assert_eq!(layer.sample(Vec2::new(1.0, 1.5)), 0.5);
 }
 
-// Helper struct to define pheromone color channels
-struct PheromoneColor {
-    r: u8,
-    g: u8,
-    b: u8,
-    a: u8,
-}
-
-// Generic function to update pheromone textures
-fn update_pheromone_texture<T: Send + Sync + 'static + PheromoneTypeInfo>(
-    mut commands: Commands,
-    pheromone_grid: Res<PheromoneGrid<T>>,
-    mut images: ResMut<Assets<Image>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-) {
-    // Create a new texture each frame instead of trying to modify the existing one
-    let window = window_query.get_single().unwrap();
-    let width = window.width() as u32;
-    let height = window.height() as u32;
+#[test]
+fn coarse_cells_cover_the_whole_world() {
+    let mut config = crate::config::SimConfig::default();
+    config.world.width = 201.;
+    config.world.height = 100.;
+    config.pheromones.cell_size = 4.;
+    config.ants.count = 10;
+    let mut app = crate::headless::headless_app(config);
+    app.update();
+
+    let grid = app.world().resource::<PheromoneGrid<Food>>();
+    assert_eq!((grid.width, grid.height), (51, 25));
+    assert_eq!(grid.layers[0].values().len(), 51 * 25);
+    assert_eq!(grid.cell_at(Vec2::new(200.5, 99.9)), (50, 24));
+    assert_eq!(grid.cell_at(Vec2::new(7.9, 4.0)), (1, 1));
+    assert_eq!(grid.grid_to_world(1, 1), Vec2::new(6., 6.));
+    assert_eq!(grid.world_to_grid(Vec2::new(6., 2.)), Vec2::new(1.5, 0.5));
+}
 
-    // Get the color for this pheromone type
-    let color = T::color();
+#[test]
+fn evaporation_halves_levels_and_clears_faint_trails() {
+    let mut layer = PheromoneLayer::new(100, 100);
+    assert!(layer.is_clear());
+    layer.set(10, 10, 1.0);
+    layer.values_mut()[CHUNK_CELLS * 2] = 0.5;
+
+    layer.evaporate(0.5, 0.1);
+    assert_eq!(layer.get(10, 10), 0.5);
+    assert_eq!(layer.values()[CHUNK_CELLS * 2], 0.25);
+
+    layer.evaporate(0.5, 0.1);
+    layer.evaporate(0.5, 0.1);
+    assert_eq!(layer.get(10, 10), 0.125);
+    assert_eq!(layer.values()[CHUNK_CELLS * 2], 0.0);
+    assert!(!layer.is_clear());
+
+    layer.evaporate(0.5, 0.1);
+    assert_eq!(layer.get(10, 10), 0.0);
+    assert!(layer.is_clear());
+}
 
-    // Create a new image with the current pheromone data
-    let mut data = vec![0u8; (width * height * 4) as usize];
+#[test]
+fn diffusion_only_wakes_the_chunks_a_trail_reaches() {
+    // Chunks of 64 rows each
+    let mut layer = PheromoneLayer::new(64, 256);
+    layer.set(10, 10, 1.0);
+    layer.set(10, 200, 1.0);
+    let mut blocked = vec![false; 64 * 256];
+    blocked[200 * 64 + 11] = true;
+
+    diffuse(&mut layer, 1, 0.5, false, &blocked);
+
+    assert_eq!(layer.active, [true, false, false, true]);
+    assert!(layer.get(11, 10) > 0.0);
+    assert_eq!(layer.get(11, 200), 0.0);
+
+    // Once the trail is gone from a chunk, it is skipped again
+    layer.values[..64 * 64].fill(0.0);
+    diffuse(&mut layer, 1, 0.5, false, &blocked);
+    assert_eq!(layer.active, [false, false, false, true]);
+}
 
-    let grid_inner = pheromone_grid.into_inner();
+#[test]
+fn trails_stay_out_of_walls_once_diffusion_is_turned_on() {
+    use crate::config::ObstacleShape;
+    use crate::headless::empty_world;
+
+    let mut app = empty_world(|config| {
+        config.pheromones.diffusion_rate = 0.0;
+        config.obstacles = vec![ObstacleShape::Rectangle {
+            center: [100., 100.],
+            size: [20., 200.],
+        }];
+    });
+    for _ in 0..10 {
+        app.update();
+    }
 
-    // Fill the texture data based on the grid values
-    for y in 0..grid_inner.height.min(height as usize) {
-        for x in 0..grid_inner.width.min(width as usize) {
-            // Get the pheromone value at this position
-            let pheromone_value = grid_inner.grid[x][y];
-
-            // Convert to a color intensity based on pheromone level
-            let intensity = (pheromone_value * 255.0).min(255.0) as u8;
-
-            // Calculate the pixel index in the texture data
-            // Flip y-coordinate to match screen coordinates (0,0 at top-left)
-            let pixel_index = (((height as usize - 1 - y) as u32 * width + x as u32) * 4) as usize;
-
-            // Set the pixel color (RGBA) with the specified color channels
-            if pixel_index + 3 < data.len() {
-                data[pixel_index] = (color.r as u16 * intensity as u16 / 255) as u8; // R
-                data[pixel_index + 1] = (color.g as u16 * intensity as u16 / 255) as u8; // G
-                data[pixel_index + 2] = (color.b as u16 * intensity as u16 / 255) as u8; // B
-                data[pixel_index + 3] = (color.a as u16 * intensity as u16 / 255) as u8; // A
-            }
-        }
+    // As a hot reload would
+    app.world_mut().resource_mut::<SimConfig>().pheromones.diffusion_rate = 0.5;
+    app.world_mut().resource_mut::<PheromoneGrid<Food>>().layers[0].set(89, 100, 1.0);
+    for _ in 0..10 {
+        app.update();
     }
 
-    // Create a new texture with the updated data
-    let mut new_texture = Image::new_fill(
-        Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
+    let layer = &app.world().resource::<PheromoneGrid<Food>>().layers[0];
+    assert!(layer.get(88, 100) > 0.0);
+    assert_eq!(layer.get(90, 100), 0.0);
+}
+
+#[test]
+fn registered_pheromones_get_their_own_grid() {
+    struct Repellent;
+
+    let mut config = SimConfig::default();
+    config.world.width = 100.;
+    config.world.height = 100.;
+    config.ants.count = 10;
+    let mut app = crate::headless::headless_app(config);
+    app.add_pheromone::<Repellent>(PheromoneSpec {
+        color: PheromoneColor {
+            r: 200,
+            g: 0,
+            b: 0,
+            a: 255,
         },
-        TextureDimension::D2,
-        &data,
-        TextureFormat::Rgba8Unorm,
-        bevy::render::render_asset::RenderAssetUsages::RENDER_WORLD,
-    );
-
-    // Set texture properties
-    new_texture.texture_descriptor.usage =
-        bevy::render::render_resource::TextureUsages::TEXTURE_BINDING
-            | bevy::render::render_resource::TextureUsages::COPY_DST;
-
-    // Add the new texture to assets and update the sprite
-    if let Some(entity) = grid_inner.texture_entity {
-        let new_handle = images.add(new_texture);
-        commands
-            .entity(entity)
-            .insert(Sprite::from_image(new_handle));
+        increment: 0.0,
+        half_life: 1.0,
+        trail: Trail::Events,
+    });
+    app.update();
+    app.world_mut()
+        .send_event(DepositPheromone::<Repellent>::new(Vec2::new(50.5, 20.5), ColonyId(0), 0.8));
+    app.update();
+
+    assert_eq!(app.world().resource::<PheromoneRegistry>().kinds().len(), 4);
+    let grid = app.world().resource::<PheromoneGrid<Repellent>>();
+    assert_eq!((grid.width, grid.height), (100, 100));
+    // One tick of evaporation at 60 ticks per second
+    let level = grid.layers[0].get(50, 20);
+    assert!((level - 0.8 * 0.5f32.powf(1. / 60.)).abs() < 1e-6, "{level}");
+    assert_eq!(grid.layers[0].values().iter().filter(|value| **value > 0.).count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "already registered")]
+fn pheromone_kinds_register_only_once() {
+    let config = SimConfig::default();
+    let spec = PheromoneSpec::food(&config.pheromones);
+    crate::headless::headless_app(config).add_pheromone::<Food>(spec);
+}
+
+#[test]
+fn trails_weaken_with_distance_from_the_last_goal() {
+    use crate::headless::{empty_world, spawn_ant};
+
+    let mut app = empty_world(|_| {});
+    let half_distance = app.world().resource::<SimConfig>().pheromones.deposit_half_distance;
+    for (y, distance) in [(30., 0.), (170., half_distance)] {
+        spawn_ant(&mut app, Vec2::new(50., y)).insert(Travelled { distance });
     }
+    app.update();
+
+    // Whatever the ants laid around where they started
+    let layer = &app.world().resource::<PheromoneGrid<Nest>>().layers[0];
+    let laid = |y: usize| -> f32 {
+        (y - 5..y + 5)
+            .flat_map(|y| (45..55).map(move |x| (x, y)))
+            .map(|(x, y)| layer.get(x, y))
+            .sum()
+    };
+    let ratio = laid(170) / laid(30);
+    assert!((ratio - 0.5).abs() < 0.01, "{ratio}");
 }
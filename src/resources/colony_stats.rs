@@ -0,0 +1,27 @@
+use crate::components::colony_id::ColonyId;
+use bevy::prelude::*;
+
+// Running totals for one colony
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColonyStat {
+    pub population: usize,
+    pub food_store: f32,
+    pub food_delivered: u64,
+    pub births: u64,
+    pub deaths: u64,
+}
+
+// Per-colony statistics, indexed by `ColonyId`
+#[derive(Resource, Debug, Default)]
+pub struct ColonyStats {
+    pub colonies: Vec<ColonyStat>,
+}
+
+impl ColonyStats {
+    pub fn colony_mut(&mut self, colony: ColonyId) -> &mut ColonyStat {
+        if self.colonies.len() <= colony.0 {
+            self.colonies.resize_with(colony.0 + 1, ColonyStat::default);
+        }
+        &mut self.colonies[colony.0]
+    }
+}
@@ -0,0 +1,39 @@
+use crate::components::colony_id::ColonyId;
+use crate::foraging::{ForagingStrategy, PheromoneFollowing};
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// The strategy each colony's ants follow unless they carry their own
+// `Strategy`. Colonies without one use the default, which starts out as
+// `PheromoneFollowing`.
+#[derive(Resource, Clone)]
+pub struct ForagingStrategies {
+    default: Arc<dyn ForagingStrategy>,
+    colonies: HashMap<ColonyId, Arc<dyn ForagingStrategy>>,
+}
+
+impl Default for ForagingStrategies {
+    fn default() -> Self {
+        Self {
+            default: Arc::new(PheromoneFollowing),
+            colonies: HashMap::new(),
+        }
+    }
+}
+
+impl ForagingStrategies {
+    pub fn set_default(&mut self, strategy: impl ForagingStrategy) -> &mut Self {
+        self.default = Arc::new(strategy);
+        self
+    }
+
+    pub fn set_colony(&mut self, colony: ColonyId, strategy: impl ForagingStrategy) -> &mut Self {
+        self.colonies.insert(colony, Arc::new(strategy));
+        self
+    }
+
+    pub fn for_colony(&self, colony: ColonyId) -> &dyn ForagingStrategy {
+        self.colonies.get(&colony).unwrap_or(&self.default).as_ref()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod colony_stats;
+pub mod foraging_strategies;
+pub mod obstacle_map;
+pub mod sim_rng;
+pub mod world_bounds;
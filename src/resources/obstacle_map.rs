@@ -0,0 +1,114 @@
+use crate::config::ObstacleShape;
+use crate::resources::world_bounds::WorldBounds;
+use bevy::prelude::*;
+
+// Which one-unit cells of the world are covered by an obstacle, so movement,
+// sensing and deposition can check a position without testing every shape
+#[derive(Resource, Debug, Default)]
+pub struct ObstacleMap {
+    width: usize,
+    height: usize,
+    // Row-major, one entry per cell
+    blocked: Vec<bool>,
+}
+
+impl ObstacleMap {
+    // Rasterises `shapes`; a cell is blocked when its centre lies in a shape
+    pub fn new(bounds: &WorldBounds, shapes: &[ObstacleShape]) -> Self {
+        // Partial cells at the far edges still count
+        let width = bounds.width.ceil() as usize;
+        let height = bounds.height.ceil() as usize;
+        let mut blocked = vec![false; width * height];
+
+        for shape in shapes {
+            let (min, max) = shape.bounding_box();
+            let x_range = (min.x.max(0.) as usize)..(max.x.ceil().max(0.) as usize).min(width);
+            let y_range = (min.y.max(0.) as usize)..(max.y.ceil().max(0.) as usize).min(height);
+            for y in y_range {
+                for x in x_range.clone() {
+                    let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    if shape.contains(center) {
+                        blocked[y * width + x] = true;
+                    }
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            blocked,
+        }
+    }
+
+    // Blocks every cell set in `cells`, a row-major mask the size of the world
+    pub fn block_cells(&mut self, cells: &[bool]) {
+        for (blocked, cell) in self.blocked.iter_mut().zip(cells) {
+            *blocked |= *cell;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.blocked.contains(&true)
+    }
+
+    // Whether `position` lies in an obstacle; positions outside the world
+    // wrap around like everything else in the arena
+    pub fn is_blocked(&self, position: Vec2) -> bool {
+        if self.blocked.is_empty() {
+            return false;
+        }
+        let x = (position.x.floor() as i64).rem_euclid(self.width as i64) as usize;
+        let y = (position.y.floor() as i64).rem_euclid(self.height as i64) as usize;
+        self.blocked[y * self.width + x]
+    }
+
+    // Whether the straight path from `from` to `to` touches an obstacle,
+    // checked every half unit so thin walls cannot be skipped over
+    pub fn is_path_blocked(&self, from: Vec2, to: Vec2) -> bool {
+        if self.blocked.is_empty() {
+            return false;
+        }
+        let steps = (from.distance(to) * 2.).ceil().max(1.) as usize;
+        (1..=steps).any(|step| self.is_blocked(from.lerp(to, step as f32 / steps as f32)))
+    }
+}
+
+#[test]
+fn cells_inside_shapes_are_blocked() {
+    let map = ObstacleMap::new(
+        &WorldBounds::new(100., 50.),
+        &[
+            ObstacleShape::Rectangle {
+                center: [50., 25.],
+                size: [2., 50.],
+            },
+            ObstacleShape::Circle {
+                center: [10., 10.],
+                radius: 3.,
+            },
+        ],
+    );
+
+    assert!(map.is_blocked(Vec2::new(50., 40.)));
+    assert!(map.is_blocked(Vec2::new(11., 11.)));
+    assert!(!map.is_blocked(Vec2::new(20., 20.)));
+    // Wrapped positions are looked up in the arena
+    assert!(map.is_blocked(Vec2::new(150.5, 25.)));
+    assert!(map.is_path_blocked(Vec2::new(45., 25.), Vec2::new(55., 25.)));
+    assert!(!map.is_path_blocked(Vec2::new(45., 25.), Vec2::new(45., 5.)));
+}
+
+#[test]
+fn partial_cells_at_the_edge_are_kept() {
+    let map = ObstacleMap::new(
+        &WorldBounds::new(10.5, 5.),
+        &[ObstacleShape::Rectangle {
+            center: [10., 2.5],
+            size: [2., 5.],
+        }],
+    );
+
+    assert!(map.is_blocked(Vec2::new(10.2, 2.)));
+    assert!(!map.is_blocked(Vec2::new(0.2, 2.)));
+}
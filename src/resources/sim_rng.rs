@@ -0,0 +1,71 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+// Independent random streams, one per kind of random decision, so systems
+// never share generator state and may run in any order or in parallel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngStream {
+    Spawn,
+    Movement,
+    Birth,
+    Starvation,
+    Food,
+    Hazard,
+}
+
+// Seeded source of all simulation randomness. Generators are derived from
+// the seed, the current tick, the stream and optionally an entity, so the
+// same seed and tick count always reproduce the same simulation state.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct SimRng {
+    seed: u64,
+    tick: u64,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, tick: 0 }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // Generator for decisions that are not tied to a single entity
+    pub fn stream(&self, stream: RngStream) -> StdRng {
+        StdRng::seed_from_u64(self.derive_seed(stream, 0))
+    }
+
+    // Generator owned by one entity for the current tick
+    pub fn entity_stream(&self, stream: RngStream, entity: Entity) -> StdRng {
+        StdRng::seed_from_u64(self.derive_seed(stream, entity.to_bits()))
+    }
+
+    fn derive_seed(&self, stream: RngStream, key: u64) -> u64 {
+        let stream_seed = splitmix64(self.seed ^ splitmix64(stream as u64));
+        splitmix64(splitmix64(stream_seed ^ self.tick) ^ key)
+    }
+}
+
+impl Default for SimRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+// Moves every stream on to the next tick
+pub fn advance_sim_rng(mut sim_rng: ResMut<SimRng>) {
+    sim_rng.tick += 1;
+}
+
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,125 @@
+use crate::config::BoundaryMode;
+use bevy::prelude::*;
+
+// Size of the simulated arena in world units, independent of any window,
+// and what happens at its edges
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct WorldBounds {
+    pub width: f32,
+    pub height: f32,
+    pub boundary: BoundaryMode,
+}
+
+// Where a step that may have left the world ends up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EdgeCrossing {
+    // Inside the world at this position, wrapped around if needed
+    Inside(Vec2),
+    // Stopped by a wall at the edge
+    Blocked,
+    // Bounced back inside; the direction is mirrored on the flagged axes
+    Reflected { position: Vec2, flip: BVec2 },
+    // Walked off into the void
+    Lost,
+}
+
+impl WorldBounds {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            boundary: BoundaryMode::default(),
+        }
+    }
+
+    pub fn with_boundary(self, boundary: BoundaryMode) -> Self {
+        Self { boundary, ..self }
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.size() / 2.
+    }
+
+    pub fn contains(&self, position: Vec2) -> bool {
+        (0.0..self.width).contains(&position.x) && (0.0..self.height).contains(&position.y)
+    }
+
+    // The in-world position to sense or deposit at for `position`, or `None`
+    // when there is nothing there
+    pub fn lookup(&self, position: Vec2) -> Option<Vec2> {
+        match self.boundary {
+            BoundaryMode::Wrap => Some(self.wrap(position)),
+            _ => self.contains(position).then_some(position),
+        }
+    }
+
+    // Applies the boundary mode to a position an ant has just stepped to
+    pub fn cross(&self, position: Vec2) -> EdgeCrossing {
+        if self.contains(position) {
+            return EdgeCrossing::Inside(position);
+        }
+        match self.boundary {
+            BoundaryMode::Wrap => EdgeCrossing::Inside(self.wrap(position)),
+            BoundaryMode::Wall => EdgeCrossing::Blocked,
+            BoundaryMode::Reflect => {
+                let (x, flip_x) = reflect(position.x, self.width);
+                let (y, flip_y) = reflect(position.y, self.height);
+                EdgeCrossing::Reflected {
+                    position: Vec2::new(x, y),
+                    flip: BVec2::new(flip_x, flip_y),
+                }
+            }
+            BoundaryMode::Void => EdgeCrossing::Lost,
+        }
+    }
+
+    fn wrap(&self, position: Vec2) -> Vec2 {
+        Vec2::new(
+            position.x.rem_euclid(self.width),
+            position.y.rem_euclid(self.height),
+        )
+    }
+}
+
+// Mirrors `value` back into `0..size`, and whether it had to be
+fn reflect(value: f32, size: f32) -> (f32, bool) {
+    if value < 0.0 {
+        ((-value).min(size.next_down()), true)
+    } else if value >= size {
+        ((2. * size - value).clamp(0.0, size.next_down()), true)
+    } else {
+        (value, false)
+    }
+}
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self::new(1728., 1050.)
+    }
+}
+
+#[test]
+fn boundary_modes_handle_leaving_the_world() {
+    let bounds = WorldBounds::new(100., 50.);
+    let outside = Vec2::new(102., 10.);
+
+    assert_eq!(bounds.cross(outside), EdgeCrossing::Inside(Vec2::new(2., 10.)));
+    assert_eq!(bounds.lookup(outside), Some(Vec2::new(2., 10.)));
+
+    let walled = bounds.with_boundary(BoundaryMode::Wall);
+    assert_eq!(walled.cross(outside), EdgeCrossing::Blocked);
+    assert_eq!(walled.lookup(outside), None);
+
+    assert_eq!(
+        bounds.with_boundary(BoundaryMode::Reflect).cross(outside),
+        EdgeCrossing::Reflected {
+            position: Vec2::new(98., 10.),
+            flip: BVec2::new(true, false),
+        }
+    );
+    assert_eq!(bounds.with_boundary(BoundaryMode::Void).cross(outside), EdgeCrossing::Lost);
+}
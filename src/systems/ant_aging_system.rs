@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+use crate::components::ant::Ant;
+use crate::components::dying::Dying;
+
+// System to age ants; an ant dies once its lifetime runs out
+pub fn ant_aging_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ant_query: Query<(Entity, &mut Ant)>,
+) {
+    for (entity, mut ant) in ant_query.iter_mut() {
+        ant.lifetime.tick(time.delta());
+        if ant.lifetime.finished() {
+            commands.entity(entity).try_insert(Dying);
+        }
+    }
+}
@@ -1,64 +1,190 @@
+use crate::ant::{AntStateChanged, set_state};
 use crate::components::ant::Ant;
+use crate::components::ant_state::AntState;
 use crate::components::carrying_food::CarryingFood;
+use crate::components::colony_id::ColonyId;
 use crate::components::food::Food;
+use crate::components::food_store::FoodStore;
+use crate::components::nest::Nest;
 use crate::components::position::Position;
+use crate::components::travelled::Travelled;
+use crate::config::SimConfig;
+use crate::nest::nearest_nest;
+use crate::resources::colony_stats::ColonyStats;
 use bevy::prelude::*;
 
+type AntGoal = (
+    Entity,
+    &'static Position,
+    &'static ColonyId,
+    Option<&'static CarryingFood>,
+    Option<&'static mut Travelled>,
+    Option<&'static mut AntState>,
+);
+
+#[allow(clippy::too_many_arguments)]
 pub fn ant_goal_system(
     mut commands: Commands,
-    mut query: Query<(Entity, &Position, Option<&CarryingFood>), With<Ant>>,
-    food_positions: Query<&Position, With<Food>>,
+    mut query: Query<AntGoal, With<Ant>>,
+    mut food_sources: Query<(Entity, &Position, &mut Food)>,
+    nests: Query<(Entity, &Position, &Nest, &ColonyId)>,
+    mut food_stores: Query<&mut FoodStore>,
+    mut stats: ResMut<ColonyStats>,
+    mut state_changes: EventWriter<AntStateChanged>,
+    config: Res<SimConfig>,
 ) {
-    // Collect food positions into a Vec to avoid query conflicts
-    let food_positions: Vec<Vec2> = food_positions
-        .iter()
-        .map(|position| Vec2::new(position.position.x, position.position.y))
-        .collect();
+    for (entity, position, colony, carrying_food, travelled, mut state) in query.iter_mut() {
+        let mut next_state = None;
+        let reached_nest = nearest_nest(&nests, *colony, position.position).filter(
+            |(_, nest_position, nest)| {
+                nest_position.position.distance(position.position) < nest.radius
+            },
+        );
+        let mut touched_goal = reached_nest.is_some();
 
-    for (entity, position, carrying_food) in query.iter_mut() {
         if carrying_food.is_none() {
-            // Check if ant found food
-            let found_food = food_positions
-                .iter()
-                .any(|&food_pos| food_pos.distance(position.position) < 5.0);
+            // Check if ant found food that is not used up yet
+            let found_food = food_sources.iter_mut().find(|(_, food_pos, food)| {
+                food.remaining > 0
+                    && food_pos.position.distance(position.position) < config.food.pickup_radius
+            });
 
-            if found_food {
-                commands.entity(entity).insert(CarryingFood);
-            }
-        } else {
-            // Check if ant reached the nest
-            let reached_nest = position.position.length() < 10.0;
+            if let Some((food_entity, _, mut food)) = found_food {
+                // The ant may die in this same tick, so don't insist on it existing
+                commands.entity(entity).try_insert(CarryingFood);
+                touched_goal = true;
+                next_state = Some(AntState::ReturningWithFood);
 
-            if reached_nest {
-                // Change goal back to finding food
-                commands.entity(entity).remove::<CarryingFood>();
+                food.remaining -= 1;
+                if food.remaining == 0 {
+                    commands.entity(food_entity).despawn();
+                }
+            }
+        } else if let Some((nest_entity, _, _)) = reached_nest {
+            // The ant reached the nearest nest of its own colony: deliver the
+            // food and change goal back to finding food
+            if let Ok(mut store) = food_stores.get_mut(nest_entity) {
+                store.amount += 1.0;
             }
+            stats.colony_mut(*colony).food_delivered += 1;
+            commands.entity(entity).remove::<CarryingFood>();
+            next_state = Some(if config.states.rest_duration > 0.0 {
+                AntState::Resting
+            } else {
+                AntState::Exploring
+            });
+        }
+        if let (Some(to), Some(state)) = (next_state, state.as_deref_mut()) {
+            set_state(&mut state_changes, entity, *colony, state, to);
+        }
+
+        // Trails are laid at full strength again from here
+        if touched_goal && let Some(mut travelled) = travelled {
+            travelled.distance = 0.0;
         }
     }
 }
 
 #[test]
 fn ant_carries_food_when_touching_food() {
-    let mut app = App::new();
-    app.add_systems(Update, ant_goal_system);
+    let mut app = test_app();
     let position = Vec2::new(100., 100.);
     add_ant_at_position(position, app.world_mut(), false);
-    app.world_mut().spawn((Food, Position { position }));
+    app.world_mut().spawn((Food::new(10), Position { position }));
 
     app.update();
 
     assert_eq!(food_carrying_ants_count(app.world_mut()), 1);
 }
 
+#[test]
+fn food_source_is_removed_once_depleted() {
+    let mut app = test_app();
+    let position = Vec2::new(100., 100.);
+    for _ in 0..3 {
+        add_ant_at_position(position, app.world_mut(), false);
+    }
+    app.world_mut().spawn((Food::new(2), Position { position }));
+
+    app.update();
+
+    assert_eq!(food_carrying_ants_count(app.world_mut()), 2);
+    let mut food_sources = app.world_mut().query::<&Food>();
+    assert_eq!(food_sources.iter(app.world()).count(), 0);
+}
+
 #[test]
 fn ant_drops_food_when_touching_nest() {
-    let mut app = App::new();
-    app.add_systems(Update, ant_goal_system);
+    let mut app = test_app();
+    app.world_mut().spawn((
+        Nest { radius: 10. },
+        ColonyId(0),
+        Position { position: Vec2::ZERO },
+        FoodStore { amount: 0. },
+    ));
     add_ant_at_position(Vec2::new(9., 0.), app.world_mut(), true);
 
     app.update();
 
     assert_eq!(food_carrying_ants_count(app.world_mut()), 0);
+    let mut stores = app.world_mut().query::<&FoodStore>();
+    assert_eq!(stores.single(app.world()).amount, 1.);
+    assert_eq!(app.world().resource::<ColonyStats>().colonies[0].food_delivered, 1);
+}
+
+#[test]
+fn ant_ignores_nest_of_another_colony() {
+    let mut app = test_app();
+    app.world_mut().spawn((
+        Nest { radius: 10. },
+        ColonyId(1),
+        Position { position: Vec2::ZERO },
+        FoodStore { amount: 0. },
+    ));
+    add_ant_at_position(Vec2::new(9., 0.), app.world_mut(), true);
+
+    app.update();
+
+    assert_eq!(food_carrying_ants_count(app.world_mut()), 1);
+    let mut stores = app.world_mut().query::<&FoodStore>();
+    assert_eq!(stores.single(app.world()).amount, 0.);
+}
+
+#[test]
+fn delivering_food_sends_the_ant_to_rest() {
+    let mut app = test_app();
+    app.world_mut().spawn((
+        Nest { radius: 10. },
+        ColonyId(0),
+        Position { position: Vec2::ZERO },
+        FoodStore { amount: 0. },
+    ));
+    let ant = add_ant_at_position(Vec2::ZERO, app.world_mut(), true);
+
+    app.update();
+
+    assert_eq!(app.world().get::<AntState>(ant), Some(&AntState::Resting));
+    let events = app.world().resource::<Events<AntStateChanged>>();
+    let changes: Vec<_> = events.get_cursor().read(events).copied().collect();
+    assert_eq!(
+        changes,
+        [AntStateChanged {
+            ant,
+            colony: ColonyId(0),
+            from: AntState::ReturningWithFood,
+            to: AntState::Resting,
+        }]
+    );
+}
+
+#[cfg(test)]
+fn test_app() -> App {
+    let mut app = App::new();
+    app.init_resource::<SimConfig>();
+    app.init_resource::<ColonyStats>();
+    app.add_event::<AntStateChanged>();
+    app.add_systems(Update, ant_goal_system);
+    app
 }
 
 #[cfg(test)]
@@ -67,15 +193,18 @@ fn food_carrying_ants_count(world: &mut World) -> usize {
 }
 
 #[cfg(test)]
-fn add_ant_at_position(position: Vec2, world: &mut World, carrying_food: bool) {
+fn add_ant_at_position(position: Vec2, world: &mut World, carrying_food: bool) -> Entity {
     let mut entity = world.spawn((
         Ant {
             lifetime: Timer::new(std::time::Duration::from_secs_f32(100.), TimerMode::Once),
         },
+        ColonyId(0),
         Position { position },
+        AntState::Exploring,
     ));
 
     if carrying_food {
-        entity.insert(CarryingFood);
+        entity.insert((CarryingFood, AntState::ReturningWithFood));
     }
+    entity.id()
 }
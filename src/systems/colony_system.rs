@@ -0,0 +1,88 @@
+use crate::ant::ant_bundle;
+use crate::components::ant::Ant;
+use crate::components::colony_id::ColonyId;
+use crate::components::dying::Dying;
+use crate::components::food_store::FoodStore;
+use crate::components::nest::Nest;
+use crate::components::position::Position;
+use crate::config::SimConfig;
+use crate::resources::colony_stats::ColonyStats;
+use crate::resources::sim_rng::{RngStream, SimRng};
+use crate::utils::geometry::random_point_in_circle;
+use bevy::prelude::*;
+use rand::Rng;
+use std::collections::HashMap;
+
+// Feeds each colony from its nest's store, hatches new ants while the store
+// is full enough and lets the colony's ants starve once it runs dry
+pub fn colony_system(
+    mut commands: Commands,
+    mut nests: Query<(Entity, &Position, &Nest, &ColonyId, &mut FoodStore)>,
+    ants: Query<(Entity, &ColonyId), With<Ant>>,
+    mut stats: ResMut<ColonyStats>,
+    config: Res<SimConfig>,
+    sim_rng: Res<SimRng>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    let mut populations: HashMap<ColonyId, usize> = HashMap::new();
+    for (_, colony) in ants.iter() {
+        *populations.entry(*colony).or_default() += 1;
+    }
+
+    for (nest_entity, nest_position, nest, colony, mut store) in nests.iter_mut() {
+        let population = populations.get(colony).copied().unwrap_or(0);
+        let consumption = population as f32 * config.ants.food_consumption * delta;
+        store.amount = (store.amount - consumption).max(0.0);
+
+        let stat = stats.colony_mut(*colony);
+        stat.population = population;
+
+        if store.amount <= 0.0 {
+            let death_chance = (config.ants.starvation_rate * delta).min(1.0);
+            for (ant, _) in ants.iter().filter(|(_, ant_colony)| *ant_colony == colony) {
+                let mut rng = sim_rng.entity_stream(RngStream::Starvation, ant);
+                if rng.gen_bool(death_chance as f64) {
+                    commands.entity(ant).try_insert(Dying);
+                }
+            }
+            stat.food_store = store.amount;
+            continue;
+        }
+
+        let mut rng = sim_rng.entity_stream(RngStream::Birth, nest_entity);
+        while store.amount > config.nest.birth_threshold {
+            store.amount -= config.nest.birth_cost;
+            let position = nest_position.position + random_point_in_circle(&mut rng, nest.radius);
+            commands.spawn(ant_bundle(position, *colony, &config, &mut rng));
+            stat.births += 1;
+        }
+        stat.food_store = store.amount;
+    }
+}
+
+#[test]
+fn full_store_hatches_new_ants() {
+    let mut app = App::new();
+    app.init_resource::<SimConfig>()
+        .init_resource::<Time>()
+        .init_resource::<ColonyStats>()
+        .insert_resource(SimRng::new(0))
+        .add_systems(Update, colony_system);
+    let config = app.world().resource::<SimConfig>().clone();
+    app.world_mut().spawn((
+        Nest { radius: 10. },
+        ColonyId(0),
+        Position { position: Vec2::ZERO },
+        FoodStore {
+            amount: config.nest.birth_threshold + 5. * config.nest.birth_cost,
+        },
+    ));
+
+    app.update();
+
+    let mut ants = app.world_mut().query::<(&Ant, &ColonyId)>();
+    assert!(ants.iter(app.world()).all(|(_, colony)| *colony == ColonyId(0)));
+    assert_eq!(ants.iter(app.world()).count(), 5);
+    assert_eq!(app.world().resource::<ColonyStats>().colonies[0].births, 5);
+}
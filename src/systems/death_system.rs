@@ -0,0 +1,43 @@
+use crate::components::colony_id::ColonyId;
+use crate::components::dying::Dying;
+use crate::resources::colony_stats::ColonyStats;
+use bevy::prelude::*;
+
+// Removes the ants that died this tick and counts each of them once
+pub fn death_system(
+    mut commands: Commands,
+    dying: Query<(Entity, &ColonyId), With<Dying>>,
+    mut stats: ResMut<ColonyStats>,
+) {
+    for (entity, colony) in dying.iter() {
+        commands.entity(entity).despawn();
+        stats.colony_mut(*colony).deaths += 1;
+    }
+}
+
+#[test]
+fn ants_dying_of_several_causes_are_counted_once() {
+    use crate::components::ant::Ant;
+    use crate::config::{HazardConfig, HazardKind};
+    use crate::headless::{empty_world, spawn_ant};
+
+    let mut app = empty_world(|config| {
+        // Certain death by poison
+        config.alarm.lethality = 1000.;
+        config.hazards = vec![HazardConfig {
+            kind: HazardKind::Poison,
+            center: [60., 40.],
+            radius: 30.,
+        }];
+    });
+
+    // Also at the end of its life
+    spawn_ant(&mut app, Vec2::new(60., 40.)).insert(Ant {
+        lifetime: Timer::from_seconds(0., TimerMode::Once),
+    });
+    app.update();
+
+    let mut ants = app.world_mut().query::<&Ant>();
+    assert_eq!(ants.iter(app.world()).count(), 0);
+    assert_eq!(app.world().resource::<ColonyStats>().colonies[0].deaths, 1);
+}
@@ -1,68 +1,350 @@
+use crate::ant::{AntStateChanged, set_state};
 use crate::components::ant::Ant;
+use crate::components::ant_state::{AntState, Rest};
 use crate::components::carrying_food::CarryingFood;
+use crate::components::colony_id::ColonyId;
+use crate::components::depositing::Depositing;
 use crate::components::direction::Direction;
+use crate::components::dying::Dying;
+use crate::components::food::Food;
+use crate::components::hazard::Hazard;
+use crate::components::nest::Nest;
 use crate::components::position::Position;
-use crate::pheromones::PheromoneGridTrait;
+use crate::components::role::Role;
+use crate::components::strategy::Strategy;
+use crate::components::travelled::Travelled;
+use crate::config::SimConfig;
+use crate::foraging::{AntContext, Landmarks, Senses};
+use crate::pheromones::{Alarm, PheromoneGrid, PheromoneGridTrait, PheromoneGrids};
+use crate::resources::foraging_strategies::ForagingStrategies;
+use crate::resources::obstacle_map::ObstacleMap;
+use crate::resources::sim_rng::{RngStream, SimRng};
+use crate::resources::world_bounds::{EdgeCrossing, WorldBounds};
 use crate::utils::geometry::*;
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
 use rand::Rng;
 
+type AntMovement = (
+    Entity,
+    &'static mut Position,
+    &'static mut Direction,
+    &'static ColonyId,
+    &'static Role,
+    &'static mut Travelled,
+    &'static mut Depositing,
+    &'static mut AntState,
+    Option<&'static mut Rest>,
+    Option<&'static Strategy>,
+    Option<&'static CarryingFood>,
+);
+
+#[allow(clippy::too_many_arguments)]
 pub fn follow_pheromones_system(
-    mut query: Query<(&mut Position, &mut Direction, Option<&CarryingFood>), With<Ant>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut commands: Commands,
+    mut query: Query<AntMovement, With<Ant>>,
+    food_sources: Query<(&Position, &Food), Without<Ant>>,
+    nests: Query<(&Position, &ColonyId, &Nest), Without<Ant>>,
+    hazards: Query<(&Position, &Hazard), Without<Ant>>,
+    mut state_changes: EventWriter<AntStateChanged>,
+    strategies: Res<ForagingStrategies>,
+    bounds: Res<WorldBounds>,
+    obstacles: Res<ObstacleMap>,
+    sim_rng: Res<SimRng>,
+    time: Res<Time>,
+    config: Res<SimConfig>,
     food_pheromones: Res<crate::pheromones::PheromoneGrid<crate::pheromones::Food>>,
     nest_pheromones: Res<crate::pheromones::PheromoneGrid<crate::pheromones::Nest>>,
+    alarm_pheromones: Res<PheromoneGrid<Alarm>>,
+    grids: PheromoneGrids,
 ) {
-    const VIEW_ANGLE: f32 = 45.0; // in degrees
-    let mut rng = rand::thread_rng();
-    let view_radius: i32 = 6;
+    let landmarks = Landmarks {
+        food: food_sources
+            .iter()
+            .filter(|(_, food)| food.remaining > 0)
+            .map(|(position, _)| position.position)
+            .collect(),
+        nests: nests
+            .iter()
+            .map(|(position, colony, _)| (*colony, position.position))
+            .collect(),
+        hazards: hazards
+            .iter()
+            .map(|(position, hazard)| (position.position, *hazard))
+            .collect(),
+    };
 
-    for (mut position, mut direction, carrying_food) in query.iter_mut() {
+    for (
+        entity,
+        mut position,
+        mut direction,
+        colony,
+        role,
+        mut travelled,
+        mut depositing,
+        mut state,
+        mut rest,
+        strategy,
+        carrying_food,
+    ) in query.iter_mut()
+    {
+        let mut rng = sim_rng.entity_stream(RngStream::Movement, entity);
         let pheromone_grid: &dyn PheromoneGridTrait = if carrying_food.is_some() {
             &*nest_pheromones
         } else {
             &*food_pheromones
         };
 
-        let mut best_direction = direction.direction;
-        let mut max_pheromone = 0.0;
+        // Alarms are rare, so skip sensing them when the colony has none
+        let alarm = !alarm_pheromones.layer(*colony).is_clear();
+        let ant = AntContext {
+            position: position.position,
+            heading: direction.direction,
+            colony: *colony,
+            role: *role,
+            state: *state,
+            carrying_food: carrying_food.is_some(),
+            travelled: travelled.distance,
+            delta: time.delta_secs(),
+            config: &config,
+            senses: Senses {
+                bounds: &bounds,
+                obstacles: &obstacles,
+                trail: pheromone_grid,
+                alarm: alarm.then_some(&*alarm_pheromones),
+                grids: &grids,
+                colony: *colony,
+            },
+            landmarks: &landmarks,
+        };
+        // An ant's own strategy takes precedence over its colony's
+        let decision = match strategy {
+            Some(Strategy(strategy)) => strategy.decide(&ant, &mut rng),
+            None => strategies.for_colony(*colony).decide(&ant, &mut rng),
+        };
 
-        for angle in (-VIEW_ANGLE as i32..=VIEW_ANGLE as i32).step_by(1) {
-            let angle_rad = (angle as f32).to_radians();
-            let rotated_direction = rotate_vector(direction.direction, angle_rad.to_degrees());
+        let mut next_state = decision.state.unwrap_or(*state);
+        if *state == AntState::Resting {
+            // Resting ants wait out their rest whatever their strategy says
+            match rest.as_mut() {
+                Some(rest) => {
+                    if !rest.timer.tick(time.delta()).finished() {
+                        next_state = AntState::Resting;
+                    } else if next_state == AntState::Resting {
+                        next_state = AntState::Exploring;
+                    }
+                }
+                None => {
+                    let rest = Rest::new(config.states.rest_duration);
+                    commands.entity(entity).try_insert(rest);
+                    next_state = AntState::Resting;
+                }
+            }
+        }
+        if next_state != AntState::Resting && rest.is_some() {
+            commands.entity(entity).remove::<Rest>();
+        }
+        set_state(&mut state_changes, entity, *colony, &mut state, next_state);
 
-            for dist in 1..=view_radius {
-                let check_position = position.position + rotated_direction * dist as f32;
-                let pheromone_value = get_pheromone_value(check_position, pheromone_grid);
+        let rule = config.states.rule(next_state);
+        // A strategy without an opinion keeps the ant facing the same way
+        direction.direction = decision.heading.try_normalize().unwrap_or(direction.direction);
+        depositing.active = decision.deposit && rule.deposit;
+        let step = config.ants.speed * rule.speed * time.delta_secs();
+        let next_position = position.position + decision.step.normalize_or_zero() * step;
 
-                if pheromone_value > max_pheromone {
-                    max_pheromone = pheromone_value;
-                    best_direction = rotated_direction;
-                }
+        let next_position = match bounds.cross(next_position) {
+            EdgeCrossing::Inside(wrapped)
+                if !obstacles.is_path_blocked(position.position, next_position) =>
+            {
+                wrapped
+            }
+            EdgeCrossing::Reflected { position: reflected, flip }
+                if !obstacles.is_blocked(reflected) =>
+            {
+                direction.direction = Vec2::select(flip, -direction.direction, direction.direction);
+                reflected
             }
+            EdgeCrossing::Lost => {
+                commands.entity(entity).try_insert(Dying);
+                continue;
+            }
+            _ => {
+                // Stay put and turn away from the wall or obstacle
+                let turn: f32 = rng.gen_range(-90.0..=90.0);
+                direction.direction =
+                    rotate_vector(-direction.direction, turn).normalize_or(direction.direction);
+                continue;
+            }
+        };
+        travelled.distance += step;
+        position.position = next_position;
+    }
+}
+
+#[test]
+fn ants_do_not_walk_through_walls() {
+    use crate::config::{ObstacleShape, SimConfig};
+
+    let mut config = SimConfig::default();
+    config.world.width = 300.;
+    config.world.height = 200.;
+    config.simulation.seed = Some(3);
+    config.ants.count = 200;
+    config.colonies[0].nest = Some([75., 100.]);
+    // Walls on both sides of the nest, including across the wrapping edge
+    config.obstacles = vec![
+        ObstacleShape::Rectangle {
+            center: [5., 100.],
+            size: [10., 200.],
+        },
+        ObstacleShape::Rectangle {
+            center: [150., 100.],
+            size: [20., 200.],
+        },
+    ];
+    let mut app = crate::headless::headless_app(config);
+    for _ in 0..300 {
+        app.update();
+    }
+
+    let mut ants = app.world_mut().query_filtered::<&Position, With<Ant>>();
+    assert!(ants.iter(app.world()).count() > 0);
+    for position in ants.iter(app.world()) {
+        assert!((10.0..140.0).contains(&position.position.x), "{}", position.position);
+    }
+}
+
+#[test]
+fn ants_walking_into_the_void_are_lost() {
+    use crate::config::{BoundaryMode, SimConfig};
+    use crate::resources::colony_stats::ColonyStats;
+
+    let mut config = SimConfig::default();
+    config.world.width = 20.;
+    config.world.height = 20.;
+    config.world.boundary = BoundaryMode::Void;
+    config.simulation.seed = Some(3);
+    config.ants.count = 100;
+    let mut app = crate::headless::headless_app(config);
+    for _ in 0..200 {
+        app.update();
+    }
+
+    let mut ants = app.world_mut().query_filtered::<&Position, With<Ant>>();
+    let remaining = ants.iter(app.world()).count() as u64;
+    let stats = &app.world().resource::<ColonyStats>().colonies[0];
+    assert!(stats.deaths > 0);
+    assert_eq!(100 + stats.births - stats.deaths, remaining);
+}
+
+#[test]
+fn workers_flee_alarms_and_defenders_rally_to_them() {
+    use crate::headless::{empty_world, spawn_ant};
+
+    let mut app = empty_world(|config| config.simulation.seed = Some(3));
+
+    // Both ants head straight for a band of alarm just ahead of them
+    let mut spawn = |y: f32, role: Role| {
+        spawn_ant(&mut app, Vec2::new(60., y))
+            .insert((Direction { direction: Vec2::X }, role))
+            .id()
+    };
+    let worker = spawn(40., Role::Worker);
+    let defender = spawn(160., Role::Defender);
+    let layer = &mut app.world_mut().resource_mut::<PheromoneGrid<Alarm>>().layers[0];
+    for y in 0..200 {
+        for x in 62..70 {
+            layer.set(x, y, 1.0);
         }
+    }
+    app.update();
 
-        if max_pheromone == 0.0 {
-            // If no pheromone is found, move randomly within VIEW_ANGLE
-            let random_angle_rad: f32 = rng.gen_range((-VIEW_ANGLE / 2.)..=VIEW_ANGLE / 2.);
-            direction.direction = rotate_vector(direction.direction, random_angle_rad).normalize();
-        } else {
-            direction.direction = best_direction.normalize();
+    let x = |ant| app.world().get::<Position>(ant).unwrap().position.x;
+    assert!(x(worker) < 60., "{}", x(worker));
+    assert!(x(defender) > 60., "{}", x(defender));
+    assert_eq!(app.world().get::<AntState>(worker), Some(&AntState::Fleeing));
+}
+
+#[test]
+fn probes_turn_toward_the_stronger_side() {
+    use crate::headless::{empty_world, spawn_ant};
+    use crate::pheromones::Food;
+
+    let mut app = empty_world(|config| config.sensing.model = crate::config::SensingModel::Probes);
+
+    // Food trail under the left probe only
+    let ant = spawn_ant(&mut app, Vec2::new(60., 40.))
+        .insert(Direction { direction: Vec2::X })
+        .id();
+    let layer = &mut app.world_mut().resource_mut::<PheromoneGrid<Food>>().layers[0];
+    for y in 43..50 {
+        for x in 60..75 {
+            layer.set(x, y, 1.0);
         }
-        // Add some randomness to the direction
-        let random_offset: Vec2 = random_normalized_direction() * rng.gen_range(0.0..0.8);
-        position.position += (direction.direction + random_offset).normalize();
+    }
+    app.update();
+
+    // A full turn rate's worth for one tick, to the left
+    let heading = app.world().get::<Direction>(ant).unwrap().direction;
+    let turned = heading.to_angle().to_degrees();
+    let turn_rate = app.world().resource::<SimConfig>().sensing.turn_rate;
+    assert!((turned - turn_rate / 60.).abs() < 1e-3, "{turned}");
+}
+
+#[test]
+fn resting_ants_stay_put_until_rested() {
+    use crate::headless::{empty_world, spawn_ant};
+
+    let mut app = empty_world(|config| config.states.rest_duration = 0.5);
 
-        let window = window_query.get_single().unwrap();
-        position.position.x = position.position.x.rem_euclid(window.width());
-        position.position.y = position.position.y.rem_euclid(window.height());
+    let start = Vec2::new(60., 40.);
+    let ant = spawn_ant(&mut app, start).insert(AntState::Resting).id();
+    for _ in 0..20 {
+        app.update();
     }
+    assert_eq!(app.world().get::<Position>(ant).unwrap().position, start);
+
+    for _ in 0..20 {
+        app.update();
+    }
+    assert_eq!(app.world().get::<AntState>(ant), Some(&AntState::Exploring));
+    assert_ne!(app.world().get::<Position>(ant).unwrap().position, start);
 }
 
-// Helper function to get pheromone value at a position
-fn get_pheromone_value(position: Vec2, pheromone_grid: &dyn PheromoneGridTrait) -> f32 {
-    let x = position.x as usize % pheromone_grid.get_width();
-    let y = position.y as usize % pheromone_grid.get_height();
-    pheromone_grid.get_grid()[x][y]
+#[test]
+fn strategies_without_a_heading_keep_the_ant_facing_its_way() {
+    use crate::config::ObstacleShape;
+    use crate::foraging::{Decision, ForagingStrategy};
+    use crate::headless::{empty_world, spawn_ant};
+    use rand::rngs::StdRng;
+
+    // Pushes into the wall without saying where to face
+    struct Pushy;
+    impl ForagingStrategy for Pushy {
+        fn decide(&self, _: &AntContext, _: &mut StdRng) -> Decision {
+            Decision {
+                step: Vec2::X,
+                ..Decision::heading(Vec2::ZERO)
+            }
+        }
+    }
+
+    let mut app = empty_world(|config| {
+        config.obstacles = vec![ObstacleShape::Rectangle {
+            center: [100., 100.],
+            size: [20., 200.],
+        }];
+    });
+    app.world_mut()
+        .resource_mut::<ForagingStrategies>()
+        .set_colony(ColonyId(0), Pushy);
+    let ant = spawn_ant(&mut app, Vec2::new(89.5, 40.)).id();
+    for _ in 0..10 {
+        app.update();
+    }
+
+    let position = app.world().get::<Position>(ant).unwrap().position;
+    let heading = app.world().get::<Direction>(ant).unwrap().direction;
+    assert!(position.is_finite(), "{position}");
+    assert!(heading.is_normalized(), "{heading}");
 }
@@ -1,5 +1,9 @@
+pub mod ant_aging_system;
 pub mod ant_goal_system;
-pub mod ant_lifetime_reset_system;
-pub mod ant_rebirth_system;
+pub mod colony_system;
+pub mod death_system;
 pub mod follow_pheromone_system;
+#[cfg(feature = "viewer")]
 pub mod setup_pheromone_texture;
+#[cfg(feature = "viewer")]
+pub mod update_pheromone_texture;
@@ -1,18 +1,26 @@
-use crate::pheromones::PheromoneGrid;
+use crate::pheromones::{Nest, PheromoneGrid};
+use crate::resources::world_bounds::WorldBounds;
 use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
 use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
-use bevy::window::PrimaryWindow;
+
+// The single texture both pheromone types are drawn into. It is kept in the
+// main world so that it can be rewritten in place.
+#[derive(Resource)]
+pub struct PheromoneTexture {
+    pub handle: Handle<Image>,
+}
 
 // Setup pheromone texture
-pub fn setup_pheromone_texture<T: Send + Sync + 'static>(
+pub fn setup_pheromone_texture(
     mut commands: Commands,
-    pheromone_grid: ResMut<PheromoneGrid<T>>,
+    pheromone_grid: Res<PheromoneGrid<Nest>>,
     mut images: ResMut<Assets<Image>>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
+    bounds: Res<WorldBounds>,
 ) {
-    let window = window_query.get_single().unwrap();
-    let width = window.width() as u32;
-    let height = window.height() as u32;
+    // One texel per pheromone cell; every pheromone type shares the grid size
+    let width = pheromone_grid.width as u32;
+    let height = pheromone_grid.height as u32;
 
     // Create a new image
     let mut texture = Image::new_fill(
@@ -22,9 +30,9 @@ pub fn setup_pheromone_texture<T: Send + Sync + 'static>(
             depth_or_array_layers: 1,
         },
         TextureDimension::D2,
-        &[0, 0, 0, 255], // Initial color (black with full alpha)
+        &[0, 0, 0, 0], // Initially transparent
         TextureFormat::Rgba8Unorm,
-        bevy::render::render_asset::RenderAssetUsages::RENDER_WORLD,
+        RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
     );
 
     // Set texture to be filtered in nearest mode and allow it to be updated
@@ -34,12 +42,8 @@ pub fn setup_pheromone_texture<T: Send + Sync + 'static>(
     // Add the texture to the assets
     let texture_handle = images.add(texture);
 
-    // Store the handle in the resource
-    let grid_inner = pheromone_grid.into_inner();
-    grid_inner.texture_handle = Some(texture_handle.clone());
-
-    let width = window.width();
-    let height = window.height();
+    let width = bounds.width;
+    let height = bounds.height;
     commands.spawn((
         Sprite {
             color: Color::WHITE,                         // White background
@@ -51,18 +55,24 @@ pub fn setup_pheromone_texture<T: Send + Sync + 'static>(
         InheritedVisibility::default(),
     ));
 
-    // Spawn the sprite entity and store its entity ID
-    let entity = commands
-        .spawn((
-            Sprite::from_image(texture_handle.clone()),
-            Transform {
-                translation: Vec3::new(width / 2., height / 2., -1.0),
-                scale: Vec3::ONE,
-                ..default()
-            },
-        ))
-        .id();
+    // One texel per cell, so the last cells stick out past the world when the
+    // cell size doesn't divide it
+    let grid_size = Vec2::new(pheromone_grid.width as f32, pheromone_grid.height as f32)
+        * pheromone_grid.cell_size;
+    commands.spawn((
+        Sprite {
+            image: texture_handle.clone(),
+            custom_size: Some(grid_size),
+            ..default()
+        },
+        Transform {
+            translation: (grid_size / 2.).extend(-1.0),
+            scale: Vec3::ONE,
+            ..default()
+        },
+    ));
 
-    // Store the entity ID in the resource
-    grid_inner.texture_entity = Some(entity);
+    commands.insert_resource(PheromoneTexture {
+        handle: texture_handle,
+    });
 }
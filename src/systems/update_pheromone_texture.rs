@@ -0,0 +1,98 @@
+use crate::pheromones::{PheromoneColor, PheromoneLayer, PheromoneRegistry};
+use crate::systems::setup_pheromone_texture::PheromoneTexture;
+use bevy::prelude::*;
+use rayon::prelude::*;
+
+// Redraws every registered pheromone kind into the existing texture. It needs
+// the whole world to reach grids of kinds it doesn't know the type of.
+pub fn update_pheromone_texture(world: &mut World) {
+    world.resource_scope(|world, mut images: Mut<Assets<Image>>| {
+        let registry = world.resource::<PheromoneRegistry>();
+        // Trails only change on simulation ticks, which many frames don't have
+        if !registry.kinds().iter().any(|kind| kind.is_changed(world)) {
+            return;
+        }
+        let texture = world.resource::<PheromoneTexture>();
+        let Some(image) = images.get_mut(&texture.handle) else {
+            return;
+        };
+
+        // Later kinds are drawn over earlier ones, food trails over nest trails
+        let kinds: Vec<_> = registry
+            .kinds()
+            .iter()
+            .map(|kind| (kind.layers(world), kind.color))
+            .collect();
+        let width = image.texture_descriptor.size.width as usize;
+        composite_pheromones(&mut image.data, width, &kinds);
+    });
+}
+
+// Fills RGBA pixels with the strongest trail of any colony for each pheromone
+// type, blending later types over earlier ones. Texture rows run top to bottom
+// while grid rows start at the bottom of the world.
+pub fn composite_pheromones(
+    data: &mut [u8],
+    width: usize,
+    types: &[(&[PheromoneLayer], PheromoneColor)],
+) {
+    data.par_chunks_exact_mut(width * 4)
+        .rev()
+        .enumerate()
+        .for_each(|(y, pixels)| {
+            for (x, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+                let mut blended = [0u16; 4];
+                for (layers, color) in types {
+                    let pheromone_value = layers
+                        .iter()
+                        .map(|layer| layer.values()[y * width + x])
+                        .fold(0.0, f32::max);
+
+                    // Convert to a color intensity based on pheromone level
+                    let intensity = (pheromone_value * 255.0).min(255.0) as u16;
+                    let top = [color.r, color.g, color.b, color.a]
+                        .map(|channel| channel as u16 * intensity / 255);
+
+                    // Premultiplied "over": the lower type shows through
+                    // wherever the upper one is faint
+                    let coverage = 255 - top[3];
+                    for (channel, top) in blended.iter_mut().zip(top) {
+                        *channel = (top + *channel * coverage / 255).min(255);
+                    }
+                }
+                for (channel, value) in pixel.iter_mut().zip(blended) {
+                    *channel = value as u8;
+                }
+            }
+        });
+}
+
+#[test]
+fn food_trails_are_drawn_over_nest_trails() {
+    let mut nest = PheromoneLayer::new(2, 2);
+    nest.set(0, 0, 1.0);
+    nest.set(1, 0, 1.0);
+    let mut food = PheromoneLayer::new(2, 2);
+    food.set(1, 0, 1.0);
+    food.set(1, 1, 0.5);
+    let (nest, food) = (vec![nest], vec![food]);
+    let config = crate::config::PheromoneConfig::default();
+    let (nest_color, food_color) = (
+        crate::pheromones::PheromoneSpec::nest(&config).color,
+        crate::pheromones::PheromoneSpec::food(&config).color,
+    );
+
+    let mut data = vec![7u8; 2 * 2 * 4];
+    composite_pheromones(
+        &mut data,
+        2,
+        &[(&nest[..], nest_color), (&food[..], food_color)],
+    );
+
+    // The bottom grid row is the last texture row
+    let pixel = |x: usize, y: usize| &data[((1 - y) * 2 + x) * 4..][..4];
+    assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+    assert_eq!(pixel(1, 0), [20, 100, 20, 255]);
+    assert_eq!(pixel(0, 1), [0, 0, 0, 0]);
+    assert_eq!(pixel(1, 1), [9, 49, 9, 127]);
+}
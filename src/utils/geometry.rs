@@ -13,8 +13,14 @@ pub fn rotate_vector(vec: Vec2, angle_deg: f32) -> Vec2 {
     )
 }
 
-pub fn random_normalized_direction() -> Vec2 {
-    let mut rng = rand::thread_rng();
+// Uniformly distributed point in the disc of `radius` around the origin
+pub fn random_point_in_circle(rng: &mut impl Rng, radius: f32) -> Vec2 {
+    let angle = rng.gen_range(0.0..TAU);
+    let distance = radius * rng.gen_range(0.0f32..1.0).sqrt();
+    Vec2::new(angle.cos(), angle.sin()) * distance
+}
+
+pub fn random_normalized_direction(rng: &mut impl Rng) -> Vec2 {
     let random_angle = rng.gen_range(0.0..TAU); // Random angle in radians
     Vec2::new(random_angle.cos(), random_angle.sin()).normalize()
 }
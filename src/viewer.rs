@@ -0,0 +1,307 @@
+use crate::components::ant::Ant;
+use crate::components::colony_id::ColonyId;
+use crate::components::food::Food;
+use crate::components::hazard::Hazard;
+use crate::components::nest::Nest;
+use crate::components::obstacle::Obstacle;
+use crate::components::position::Position;
+use crate::components::previous_position::PreviousPosition;
+use crate::config::{HazardKind, ObstacleShape, SimConfig};
+use crate::hazards::hazard_bundle;
+use crate::map::ArenaMap;
+use crate::pheromones::{self, setup_pheromone_grid};
+use crate::resources::world_bounds::WorldBounds;
+use crate::systems::setup_pheromone_texture::setup_pheromone_texture;
+use crate::systems::update_pheromone_texture::update_pheromone_texture;
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::window::PrimaryWindow;
+use bevy_prototype_lyon::prelude::*;
+use std::collections::HashMap;
+
+// sRGB color of walls and other obstacles
+const OBSTACLE_COLOR: [u8; 4] = [90, 90, 90, 255];
+
+// Radius of the danger markers placed with the right mouse button
+const MARKER_RADIUS: f32 = 20.0;
+
+// Everything needed to draw the simulation. The simulation plugins never
+// touch meshes, materials or windows, so they also run under MinimalPlugins.
+pub struct ViewerPlugin;
+
+impl Plugin for ViewerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ShapePlugin)
+            .add_systems(
+                Startup,
+                (
+                    setup_camera,
+                    setup_map_walls,
+                    setup_pheromone_texture.after(setup_pheromone_grid::<pheromones::Nest>),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    add_ant_visuals,
+                    add_food_visuals,
+                    add_nest_visuals,
+                    add_obstacle_visuals,
+                    add_hazard_visuals,
+                    place_danger_marker,
+                    scale_food_with_remaining_amount,
+                    sync_transform_with_position,
+                    update_pheromone_texture,
+                ),
+            );
+    }
+}
+
+fn setup_camera(mut commands: Commands, bounds: Res<WorldBounds>) {
+    let width = bounds.width;
+    let height = bounds.height;
+    commands.spawn((
+        Camera2d, // New way to spawn a 2D camera
+        Camera {
+            order: 0, // Default camera order
+            ..default()
+        },
+        // Fit the whole world into the window, whatever its resolution
+        OrthographicProjection {
+            scaling_mode: ScalingMode::AutoMin {
+                min_width: width,
+                min_height: height,
+            },
+            ..OrthographicProjection::default_2d()
+        },
+        Transform::from_xyz(width / 2., height / 2., 0.0),
+    ));
+}
+
+// The configured color of a colony
+fn colony_color(config: &SimConfig, colony: ColonyId) -> Color {
+    let [r, g, b] = config
+        .colonies
+        .get(colony.0)
+        .map_or([1., 1., 1.], |colony| colony.color);
+    Color::srgb(r, g, b)
+}
+
+fn add_ant_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &Position, &ColonyId), Added<Ant>>,
+    config: Res<SimConfig>,
+    // Ants share one mesh, and the ants of a colony one material
+    mut mesh: Local<Option<Handle<Mesh>>>,
+    mut colony_materials: Local<HashMap<ColonyId, Handle<ColorMaterial>>>,
+) {
+    if query.is_empty() {
+        return;
+    }
+    let mesh = mesh.get_or_insert_with(|| meshes.add(Rectangle::new(3., 3.)));
+
+    for (entity, position, colony) in query.iter() {
+        let material = colony_materials
+            .entry(*colony)
+            .or_insert_with(|| materials.add(colony_color(&config, *colony)));
+        commands.entity(entity).insert((
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(position.position.extend(0.)),
+        ));
+    }
+}
+
+fn add_food_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &Position), Added<Food>>,
+    config: Res<SimConfig>,
+) {
+    if query.is_empty() {
+        return;
+    }
+    let circle_material = materials.add(Color::srgb(0., 0., 0.));
+    let circle_mesh = meshes.add(Circle::new(config.food.pickup_radius));
+
+    for (entity, position) in query.iter() {
+        commands.entity(entity).insert((
+            Mesh2d(circle_mesh.clone()),
+            MeshMaterial2d(circle_material.clone()),
+            Transform::from_translation(position.position.extend(0.)),
+        ));
+    }
+}
+
+fn add_nest_visuals(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    query: Query<(Entity, &Position, &Nest, &ColonyId), Added<Nest>>,
+    config: Res<SimConfig>,
+) {
+    for (entity, position, nest, colony) in query.iter() {
+        // A darker shade of the colony's ants
+        let color = colony_color(&config, *colony).darker(0.25);
+        commands.entity(entity).insert((
+            Mesh2d(meshes.add(Circle::new(nest.radius))),
+            MeshMaterial2d(materials.add(color)),
+            Transform::from_translation(position.position.extend(0.)),
+        ));
+    }
+}
+
+// Walls of the arena map, drawn as one texture in the obstacle color
+fn setup_map_walls(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    map: Option<Res<ArenaMap>>,
+) {
+    let Some(map) = map else {
+        return;
+    };
+
+    // Texture rows run top to bottom, map rows bottom to top
+    let data: Vec<u8> = map
+        .walls
+        .chunks(map.width)
+        .rev()
+        .flatten()
+        .flat_map(|wall| if *wall { OBSTACLE_COLOR } else { [0; 4] })
+        .collect();
+    let texture = Image::new(
+        Extent3d {
+            width: map.width as u32,
+            height: map.height as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+
+    let size = map.bounds().size();
+    commands.spawn((
+        Sprite::from_image(images.add(texture)),
+        Transform::from_translation((size / 2.).extend(0.)),
+    ));
+}
+
+fn add_obstacle_visuals(
+    mut commands: Commands,
+    query: Query<(Entity, &Obstacle), Added<Obstacle>>,
+) {
+    for (entity, obstacle) in query.iter() {
+        let path = match &obstacle.shape {
+            ObstacleShape::Rectangle { center, size } => {
+                GeometryBuilder::build_as(&shapes::Rectangle {
+                    extents: Vec2::from(*size),
+                    origin: RectangleOrigin::CustomCenter(Vec2::from(*center)),
+                    radii: None,
+                })
+            }
+            ObstacleShape::Circle { center, radius } => {
+                GeometryBuilder::build_as(&shapes::Circle {
+                    radius: *radius,
+                    center: Vec2::from(*center),
+                })
+            }
+            ObstacleShape::Polygon { points } => GeometryBuilder::build_as(&shapes::Polygon {
+                points: points.iter().map(|point| Vec2::from(*point)).collect(),
+                closed: true,
+            }),
+        };
+        commands.entity(entity).insert((
+            ShapeBundle { path, ..default() },
+            Fill::color(Color::srgba_u8(
+                OBSTACLE_COLOR[0],
+                OBSTACLE_COLOR[1],
+                OBSTACLE_COLOR[2],
+                OBSTACLE_COLOR[3],
+            )),
+        ));
+    }
+}
+
+fn add_hazard_visuals(mut commands: Commands, query: Query<(Entity, &Hazard), Added<Hazard>>) {
+    for (entity, hazard) in query.iter() {
+        // Drawn around the origin; predators move with their position
+        let path = GeometryBuilder::build_as(&shapes::Circle {
+            radius: hazard.radius,
+            center: Vec2::ZERO,
+        });
+        let color = match hazard.kind {
+            HazardKind::Marker => Color::srgba(0.9, 0.75, 0.1, 0.35),
+            HazardKind::Poison => Color::srgba(0.5, 0.1, 0.6, 0.35),
+            HazardKind::Predator => Color::srgba(0.45, 0.2, 0.1, 0.8),
+        };
+        commands
+            .entity(entity)
+            .insert((ShapeBundle { path, ..default() }, Fill::color(color)));
+    }
+}
+
+// Right-clicking places a harmless danger marker, which ants raise the alarm
+// about like any other hazard
+fn place_danger_marker(
+    mut commands: Commands,
+    buttons: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+) {
+    if !buttons.just_pressed(MouseButton::Right) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    commands.spawn(hazard_bundle(HazardKind::Marker, position, MARKER_RADIUS));
+}
+
+// Food sources shrink as they are eaten, keeping their area proportional to
+// the amount left
+fn scale_food_with_remaining_amount(mut query: Query<(&Food, &mut Transform), Changed<Food>>) {
+    for (food, mut transform) in query.iter_mut() {
+        transform.scale = Vec3::splat(food.fraction_remaining().sqrt());
+    }
+}
+
+// Draw entities between their last two fixed-tick positions so motion stays
+// smooth when the frame rate and the simulation tick rate differ
+fn sync_transform_with_position(
+    mut query: Query<(&Position, Option<&PreviousPosition>, &mut Transform)>,
+    fixed_time: Res<Time<Fixed>>,
+    bounds: Res<WorldBounds>,
+) {
+    let alpha = fixed_time.overstep_fraction();
+    let max_step = bounds.size() / 2.;
+
+    for (position, previous, mut transform) in query.iter_mut() {
+        let rendered = match previous {
+            // Don't interpolate across the arena when an ant wraps around an edge
+            Some(previous)
+                if (position.position - previous.position)
+                    .abs()
+                    .cmplt(max_step)
+                    .all() =>
+            {
+                previous.position.lerp(position.position, alpha)
+            }
+            _ => position.position,
+        };
+        transform.translation = rendered.extend(0.0); // Update position
+    }
+}